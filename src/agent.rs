@@ -0,0 +1,709 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::brain::Action;
+use crate::camera::RobotRef;
+use crate::map::Map;
+use crate::station::ResourceKind;
+use crate::ELEVATION_SCALE;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+pub const DEFAULT_AGENT_TIMEOUT: Duration = Duration::from_millis(100);
+
+// Moyen de joindre l'agent externe
+#[derive(Clone, Debug, PartialEq)]
+pub enum AgentEndpoint {
+    // Attend la connexion d'un agent sur une adresse TCP locale
+    Listen(String),
+    // Lance l'agent et dialogue par son entrée et sa sortie standard
+    Command(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgentOptions {
+    pub endpoint: AgentEndpoint,
+    // Attente maximale d'une réponse ; sans limite, la simulation avance au pas de l'agent
+    pub timeout: Option<Duration>,
+}
+
+// Messages de la simulation, une ligne JSON chacun
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    Hello {
+        version: u32,
+        seed: u64,
+        size: usize,
+        station: [usize; 2],
+        explorers: usize,
+        extractors: usize,
+        battery_capacity: f32,
+        low_battery: f32,
+        lockstep: bool,
+    },
+    // Tick sur le point d'être joué, que la réponse doit rappeler
+    Observation {
+        tick: usize,
+        // Luminosité entre 0 (nuit) et 1 (jour)
+        daylight: f32,
+        robots: Vec<Observation>,
+    },
+    End {
+        tick: usize,
+        outcome: String,
+        collected: usize,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Observation {
+    pub id: String,
+    pub kind: String,
+    pub x: usize,
+    pub y: usize,
+    pub battery: f32,
+    pub health: f32,
+    pub status: String,
+    pub cargo: Option<String>,
+    // Site assigné à un extracteur, ou trouvé par un explorateur et pas encore signalé
+    pub target: Option<[usize; 2]>,
+    // Cases connues à portée des capteurs du robot
+    pub cells: Vec<CellObservation>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CellObservation {
+    pub x: usize,
+    pub y: usize,
+    pub obstacle: bool,
+    pub explored: bool,
+    pub resource: Option<String>,
+    pub elevation: f64,
+}
+
+// Réponse de l'agent : les robots absents gardent leur comportement intégré
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Reply {
+    pub tick: usize,
+    #[serde(default)]
+    pub actions: BTreeMap<String, Order>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Order {
+    Move { dx: i64, dy: i64 },
+    GoTo { x: i64, y: i64 },
+    Harvest,
+    GoHome,
+    Wait,
+    // Comportement intégré pour ce tick
+    Auto,
+}
+
+impl Order {
+    pub(crate) fn action(&self) -> Option<Action> {
+        match *self {
+            Order::Move { dx, dy } => Some(Action::Move(dx, dy)),
+            Order::GoTo { x, y } => Some(Action::GoTo(x, y)),
+            Order::Harvest => Some(Action::Harvest),
+            Order::GoHome => Some(Action::GoHome),
+            Order::Wait => Some(Action::Wait),
+            Order::Auto => None,
+        }
+    }
+}
+
+pub(crate) fn robot_id(robot: RobotRef) -> String {
+    match robot {
+        RobotRef::Explorer(index) => format!("explorer-{}", index),
+        RobotRef::Extractor(index) => format!("extractor-{}", index),
+    }
+}
+
+pub(crate) fn parse_robot_id(id: &str, map: &Map) -> Option<RobotRef> {
+    let (kind, index) = id.split_once('-')?;
+    let index: usize = index.parse().ok()?;
+    match kind {
+        "explorer" if index < map.robot_explorers.len() => Some(RobotRef::Explorer(index)),
+        "extractor" if index < map.robot_extractors.len() => Some(RobotRef::Extractor(index)),
+        _ => None,
+    }
+}
+
+fn resource_label(kind: ResourceKind) -> String {
+    match kind {
+        ResourceKind::Energy => "energy".to_string(),
+        ResourceKind::Minerals => "minerals".to_string(),
+    }
+}
+
+// Cases découvertes autour d'une position, dans la portée des capteurs réduite
+// par les tempêtes et la nuit comme pour le comportement intégré
+fn sensed_cells(map: &Map, (x, y): (usize, usize)) -> Vec<CellObservation> {
+    let range = map.sensor_range_at(x, y);
+    let mut cells = vec![];
+    for cy in y.saturating_sub(range)..=(y + range).min(map.size - 1) {
+        for cx in x.saturating_sub(range)..=(x + range).min(map.size - 1) {
+            if map.fog_of_war[cy][cx] {
+                continue;
+            }
+            let resource = if map.seen_energy[cy][cx] {
+                Some(resource_label(ResourceKind::Energy))
+            } else if map.seen_minerals[cy][cx] {
+                Some(resource_label(ResourceKind::Minerals))
+            } else {
+                None
+            };
+            cells.push(CellObservation {
+                x: cx,
+                y: cy,
+                obstacle: map.obstacles[cy][cx],
+                explored: map.explored[cy][cx],
+                resource,
+                elevation: map.elevation[cy][cx] * ELEVATION_SCALE,
+            });
+        }
+    }
+    cells
+}
+
+fn observations(map: &Map) -> Vec<Observation> {
+    let robots = &map.config.robots;
+    let explorers = map
+        .robot_explorers
+        .iter()
+        .enumerate()
+        .map(|(index, e)| Observation {
+            id: robot_id(RobotRef::Explorer(index)),
+            kind: "explorer".to_string(),
+            x: e.x,
+            y: e.y,
+            battery: e.battery,
+            health: e.health,
+            status: e.status(robots).to_string(),
+            cargo: None,
+            target: e.resource_position.map(|(x, y)| [x, y]),
+            cells: sensed_cells(map, (e.x, e.y)),
+        });
+    let extractors = map
+        .robot_extractors
+        .iter()
+        .enumerate()
+        .map(|(index, e)| Observation {
+            id: robot_id(RobotRef::Extractor(index)),
+            kind: "extractor".to_string(),
+            x: e.x,
+            y: e.y,
+            battery: e.battery,
+            health: e.health,
+            status: e.status(robots).to_string(),
+            cargo: e.cargo.map(resource_label),
+            target: e.target_position.map(|(x, y)| [x, y]),
+            cells: sensed_cells(map, (e.x, e.y)),
+        });
+    explorers.chain(extractors).collect()
+}
+
+// Lit les lignes reçues dans un thread pour pouvoir les attendre avec un délai
+fn spawn_reader(reader: impl Read + Send + 'static) -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines() {
+            let sent = line.map(|line| sender.send(line).is_ok());
+            if sent.ok() != Some(true) {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+// Liaison avec un agent externe qui pilote les robots tick après tick
+pub struct AgentLink {
+    writer: Box<dyn Write + Send>,
+    replies: Receiver<String>,
+    timeout: Option<Duration>,
+    stream: Option<TcpStream>,
+    child: Option<Child>,
+    connected: bool,
+    // Ticks de la mission en cours joués sans réponse à temps
+    missed: usize,
+    // Erreurs de l'agent déjà journalisées pendant la mission en cours
+    reported: HashSet<String>,
+}
+
+impl AgentLink {
+    pub fn connect(options: &AgentOptions) -> Result<AgentLink, String> {
+        let (writer, replies, stream, child): (Box<dyn Write + Send>, _, _, _) =
+            match &options.endpoint {
+                AgentEndpoint::Listen(address) => {
+                    let listener = TcpListener::bind(address)
+                        .map_err(|error| format!("{}: {}", address, error))?;
+                    eprintln!("Waiting for an agent on {}", address);
+                    let (stream, peer) = listener
+                        .accept()
+                        .map_err(|error| format!("{}: {}", address, error))?;
+                    eprintln!("Agent connected from {}", peer);
+                    let _ = stream.set_nodelay(true);
+                    let reader = stream.try_clone().map_err(|error| error.to_string())?;
+                    let writer = stream.try_clone().map_err(|error| error.to_string())?;
+                    (Box::new(writer), spawn_reader(reader), Some(stream), None)
+                }
+                AgentEndpoint::Command(command) => {
+                    let mut words = command.split_whitespace();
+                    let program = words.next().ok_or("empty agent command")?;
+                    let mut child = Command::new(program)
+                        .args(words)
+                        .stdin(Stdio::piped())
+                        .stdout(Stdio::piped())
+                        .spawn()
+                        .map_err(|error| format!("{}: {}", program, error))?;
+                    let stdin = child.stdin.take().expect("agent stdin is piped");
+                    let stdout = child.stdout.take().expect("agent stdout is piped");
+                    (Box::new(stdin), spawn_reader(stdout), None, Some(child))
+                }
+            };
+        Ok(AgentLink {
+            writer,
+            replies,
+            timeout: options.timeout,
+            stream,
+            child,
+            connected: true,
+            missed: 0,
+            reported: HashSet::new(),
+        })
+    }
+
+    fn send(&mut self, message: &Message) {
+        if !self.connected {
+            return;
+        }
+        let line = serde_json::to_string(message).expect("messages are always serializable");
+        if writeln!(self.writer, "{}", line)
+            .and_then(|_| self.writer.flush())
+            .is_err()
+        {
+            self.disconnect();
+        }
+    }
+
+    fn disconnect(&mut self) {
+        if self.connected {
+            self.connected = false;
+            eprintln!("Agent disconnected, robots fall back to their built-in behaviour.");
+        }
+    }
+
+    // Journalise chaque erreur distincte de l'agent une seule fois par mission
+    fn report_error(&mut self, map: &Map, error: String) {
+        if !self.reported.contains(&error) {
+            map.log(format_args!("Agent: {}", error));
+            self.reported.insert(error);
+        }
+    }
+
+    // Annonce une nouvelle mission
+    pub fn start(&mut self, map: &Map, seed: u64) {
+        self.missed = 0;
+        self.reported.clear();
+        self.send(&Message::Hello {
+            version: PROTOCOL_VERSION,
+            seed,
+            size: map.size,
+            station: [map.station.x, map.station.y],
+            explorers: map.robot_explorers.len(),
+            extractors: map.robot_extractors.len(),
+            battery_capacity: map.config.robots.battery_capacity,
+            low_battery: map.config.robots.low_battery,
+            lockstep: self.timeout.is_none(),
+        });
+    }
+
+    // Envoie les observations du prochain tick et dépose les ordres reçus sur la carte
+    pub fn exchange(&mut self, map: &mut Map) {
+        if !self.connected || map.game_over {
+            return;
+        }
+        let tick = map.tick + 1;
+        self.send(&Message::Observation {
+            tick,
+            daylight: map.daylight(),
+            robots: observations(map),
+        });
+
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let line = match deadline {
+                Some(deadline) => self
+                    .replies
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                None => self
+                    .replies
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            let line = match line {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    self.missed += 1;
+                    map.log(format_args!(
+                        "Agent did not answer tick {} in time, robots use their built-in behaviour.",
+                        tick
+                    ));
+                    return;
+                }
+                Err(RecvTimeoutError::Disconnected) => return self.disconnect(),
+            };
+            let reply: Reply = match serde_json::from_str(&line) {
+                Ok(reply) => reply,
+                Err(error) => {
+                    self.report_error(map, format!("ignored invalid reply: {}", error));
+                    continue;
+                }
+            };
+            // Une réponse arrivée après son délai concerne un tick déjà joué
+            if reply.tick != tick {
+                continue;
+            }
+            map.orders.clear();
+            for (id, order) in &reply.actions {
+                match parse_robot_id(id, map) {
+                    Some(robot) => {
+                        if let Some(action) = order.action() {
+                            map.orders.push((robot, action));
+                        }
+                    }
+                    None => {
+                        self.report_error(map, format!("ignored order for unknown robot '{}'", id))
+                    }
+                }
+            }
+            return;
+        }
+    }
+
+    pub fn finish(&mut self, map: &Map) {
+        if self.missed > 0 {
+            eprintln!(
+                "The agent missed the deadline on {} of {} ticks",
+                self.missed, map.tick
+            );
+        }
+        let outcome = map.outcome.map_or_else(
+            || "interrupted".to_string(),
+            |outcome| format!("{:?}", outcome),
+        );
+        self.send(&Message::End {
+            tick: map.tick,
+            outcome,
+            collected: map.collected,
+        });
+    }
+}
+
+impl Drop for AgentLink {
+    // Ferme le canal pour que l'agent voie la fin du flux, puis attend le processus
+    fn drop(&mut self) {
+        self.writer = Box::new(io::sink());
+        if let Some(stream) = &self.stream {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        if let Some(child) = &mut self.child {
+            let _ = child.wait();
+        }
+    }
+}
+
+// Agent de démonstration : les explorateurs visitent les cases voisines inexplorées,
+// les extracteurs vont chercher leur site eux-mêmes ; le reste est laissé au comportement intégré
+pub struct StandIn {
+    station: [usize; 2],
+    low_battery: f32,
+}
+
+impl StandIn {
+    fn decide(&self, robot: &Observation) -> Order {
+        match robot.kind.as_str() {
+            "explorer" => {
+                if robot.target.is_some() || robot.battery < self.low_battery {
+                    return if [robot.x, robot.y] == self.station {
+                        Order::Auto
+                    } else {
+                        Order::GoHome
+                    };
+                }
+                robot
+                    .cells
+                    .iter()
+                    .find(|cell| {
+                        !cell.explored && !cell.obstacle && [cell.x, cell.y] != self.station
+                    })
+                    .map_or(Order::Auto, |cell| Order::GoTo {
+                        x: cell.x as i64,
+                        y: cell.y as i64,
+                    })
+            }
+            _ => match (&robot.cargo, robot.target) {
+                (Some(_), _) => Order::GoHome,
+                (None, Some([x, y])) if [robot.x, robot.y] == [x, y] => Order::Harvest,
+                (None, Some([x, y])) => Order::GoTo {
+                    x: x as i64,
+                    y: y as i64,
+                },
+                (None, None) => Order::Auto,
+            },
+        }
+    }
+
+    // Répond à la simulation jusqu'à la fin du flux, après `delay` pour chaque tick
+    pub fn serve(
+        reader: impl Read,
+        mut writer: impl Write,
+        delay: Duration,
+    ) -> Result<usize, String> {
+        let mut agent = StandIn {
+            station: [0, 0],
+            low_battery: 0.0,
+        };
+        let mut missions = 0;
+        for line in BufReader::new(reader).lines() {
+            let line = line.map_err(|error| error.to_string())?;
+            let message: Message = serde_json::from_str(&line)
+                .map_err(|error| format!("invalid message from the simulation: {}", error))?;
+            match message {
+                Message::Hello {
+                    version,
+                    station,
+                    low_battery,
+                    ..
+                } => {
+                    if version != PROTOCOL_VERSION {
+                        return Err(format!("unsupported protocol version {}", version));
+                    }
+                    agent = StandIn {
+                        station,
+                        low_battery,
+                    };
+                }
+                Message::Observation { tick, robots, .. } => {
+                    thread::sleep(delay);
+                    let reply = Reply {
+                        tick,
+                        actions: robots
+                            .iter()
+                            .map(|robot| (robot.id.clone(), agent.decide(robot)))
+                            .collect(),
+                    };
+                    let line = serde_json::to_string(&reply).expect("replies are serializable");
+                    writeln!(writer, "{}", line)
+                        .and_then(|_| writer.flush())
+                        .map_err(|error| error.to_string())?;
+                }
+                Message::End { .. } => missions += 1,
+            }
+        }
+        Ok(missions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hazards::{ActiveEvent, Event, EventKind};
+    use crate::mission::MissionConfig;
+    use crate::replay::state_hash;
+    use crate::terrain::GenerationSettings;
+
+    const SEED: u64 = 4;
+
+    // Liaison reliée à une socket locale ; l'autre extrémité joue le rôle de l'agent
+    fn socket_pair(timeout: Option<Duration>) -> (AgentLink, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let agent = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let _ = stream.set_nodelay(true);
+        let _ = agent.set_nodelay(true);
+        let link = AgentLink {
+            writer: Box::new(stream.try_clone().unwrap()),
+            replies: spawn_reader(stream.try_clone().unwrap()),
+            timeout,
+            stream: Some(stream),
+            child: None,
+            connected: true,
+            missed: 0,
+            reported: HashSet::new(),
+        };
+        (link, agent)
+    }
+
+    fn mission() -> Map {
+        let (mut map, _) = Map::generate(
+            SEED,
+            &GenerationSettings::default(),
+            &MissionConfig::default(),
+        );
+        map.verbose = false;
+        map
+    }
+
+    fn receive(reader: &mut impl BufRead) -> Message {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[test]
+    fn stand_in_pilots_a_mission_until_the_link_closes() {
+        let (mut link, agent) = socket_pair(None);
+        let reader = agent.try_clone().unwrap();
+        let stand_in = thread::spawn(move || StandIn::serve(reader, agent, Duration::ZERO));
+
+        let mut map = mission();
+        link.start(&map, SEED);
+        let mut orders = 0;
+        for _ in 0..30 {
+            link.exchange(&mut map);
+            orders += map.orders.len();
+            map.update_robot();
+        }
+        link.finish(&map);
+        drop(link);
+
+        assert!(orders > 0);
+        assert_eq!(stand_in.join().unwrap(), Ok(1));
+    }
+
+    #[test]
+    fn exchange_keeps_only_the_reply_to_the_current_tick() {
+        let (mut link, mut agent) = socket_pair(Some(Duration::from_secs(5)));
+        let mut reader = BufReader::new(agent.try_clone().unwrap());
+        let mut map = mission();
+
+        link.start(&map, SEED);
+        match receive(&mut reader) {
+            Message::Hello {
+                version,
+                seed,
+                size,
+                station,
+                explorers,
+                extractors,
+                lockstep,
+                ..
+            } => {
+                assert_eq!((version, seed, size), (PROTOCOL_VERSION, SEED, map.size));
+                assert_eq!(station, [map.station.x, map.station.y]);
+                assert_eq!(explorers, map.robot_explorers.len());
+                assert_eq!(extractors, map.robot_extractors.len());
+                assert!(!lockstep);
+            }
+            other => panic!("expected a hello, got {:?}", other),
+        }
+
+        // Une réponse en retard, une ligne illisible puis la bonne réponse
+        writeln!(
+            agent,
+            r#"{{"tick": 0, "actions": {{"explorer-0": {{"action": "go_home"}}}}}}"#
+        )
+        .unwrap();
+        writeln!(agent, "not json").unwrap();
+        writeln!(
+            agent,
+            r#"{{"tick": 1, "actions": {{"explorer-0": {{"action": "wait"}}, "explorer-99": {{"action": "wait"}}, "drone-0": {{"action": "wait"}}}}}}"#
+        )
+        .unwrap();
+        link.exchange(&mut map);
+        match receive(&mut reader) {
+            Message::Observation { tick, robots, .. } => {
+                assert_eq!(tick, 1);
+                assert_eq!(
+                    robots.len(),
+                    map.robot_explorers.len() + map.robot_extractors.len()
+                );
+            }
+            other => panic!("expected an observation, got {:?}", other),
+        }
+        assert_eq!(map.orders, vec![(RobotRef::Explorer(0), Action::Wait)]);
+        let mut reported: Vec<_> = link.reported.iter().cloned().collect();
+        reported.sort();
+        assert_eq!(reported.len(), 3);
+        assert!(reported[0].starts_with("ignored invalid reply: "));
+        assert_eq!(reported[1], "ignored order for unknown robot 'drone-0'");
+        assert_eq!(reported[2], "ignored order for unknown robot 'explorer-99'");
+        map.update_robot();
+
+        // La même erreur n'est signalée qu'une fois
+        writeln!(agent, "not json").unwrap();
+        writeln!(agent, r#"{{"tick": 2}}"#).unwrap();
+        link.exchange(&mut map);
+        assert_eq!(link.reported.len(), 3);
+        assert!(map.orders.is_empty());
+        map.update_robot();
+    }
+
+    #[test]
+    fn a_late_agent_leaves_the_tick_to_the_built_in_behaviour() {
+        let (mut link, _agent) = socket_pair(Some(Duration::from_millis(20)));
+        let mut map = mission();
+        link.start(&map, SEED);
+        link.exchange(&mut map);
+        assert_eq!(link.missed, 1);
+        assert!(link.connected);
+        assert!(map.orders.is_empty());
+
+        // Le tick se joue comme sans agent
+        let mut reference = mission();
+        reference.update_robot();
+        map.update_robot();
+        assert_eq!(state_hash(&map), state_hash(&reference));
+    }
+
+    #[test]
+    fn observations_follow_the_sensor_range_of_storms() {
+        let mut map = mission();
+        let (x, y) = (map.robot_explorers[0].x, map.robot_explorers[0].y);
+        assert!(sensed_cells(&map, (x, y)).len() > 1);
+
+        map.events.push(ActiveEvent {
+            event: Event {
+                kind: EventKind::DustStorm { duration: 5 },
+                x,
+                y,
+                radius: 0,
+            },
+            until: map.tick + 4,
+        });
+        let cells = sensed_cells(&map, (x, y));
+        assert_eq!(
+            cells
+                .iter()
+                .map(|cell| (cell.x, cell.y))
+                .collect::<Vec<_>>(),
+            [(x, y)]
+        );
+    }
+
+    #[test]
+    fn a_closed_agent_disconnects_the_link() {
+        let (mut link, agent) = socket_pair(None);
+        let mut map = mission();
+        link.start(&map, SEED);
+        agent.shutdown(Shutdown::Both).unwrap();
+        drop(agent);
+
+        // Sans délai de réponse, l'échange ne doit pas rester bloqué
+        link.exchange(&mut map);
+        assert!(!link.connected);
+        link.exchange(&mut map);
+        assert!(map.orders.is_empty());
+    }
+}
@@ -0,0 +1,301 @@
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::map::{DispatchMode, Map};
+use crate::mission::{ExplorerStrategy, MissionConfig, MissionSummary};
+use crate::terrain::GenerationSettings;
+
+// Balayage de paramètres : chaque configuration est jouée sur toutes les graines
+pub struct Sweep {
+    pub seeds: RangeInclusive<u64>,
+    pub strategies: Vec<ExplorerStrategy>,
+    // Flottes (explorateurs, extracteurs)
+    pub fleets: Vec<(usize, usize)>,
+    pub dispatch_modes: Vec<DispatchMode>,
+    pub config: MissionConfig,
+    pub settings: GenerationSettings,
+    pub threads: usize,
+}
+
+#[derive(Copy, Clone)]
+struct Configuration {
+    strategy: ExplorerStrategy,
+    fleet: (usize, usize),
+    dispatch_mode: DispatchMode,
+}
+
+// Moyenne, écart type et percentiles d'une mesure sur toutes les missions
+#[derive(Clone, Debug, Serialize)]
+pub struct Stats {
+    pub mean: f64,
+    pub stddev: f64,
+    pub min: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl Stats {
+    pub fn new(values: &[f64]) -> Stats {
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let n = sorted.len().max(1) as f64;
+        let mean = sorted.iter().sum::<f64>() / n;
+        let variance = if sorted.len() > 1 {
+            sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)
+        } else {
+            0.0
+        };
+        // Percentile au rang le plus proche
+        let percentile = |p: f64| {
+            let rank = ((p / 100.0 * n).ceil() as usize).clamp(1, sorted.len().max(1));
+            sorted.get(rank - 1).copied().unwrap_or(0.0)
+        };
+        Stats {
+            mean,
+            stddev: variance.sqrt(),
+            min: percentile(0.0),
+            p50: percentile(50.0),
+            p90: percentile(90.0),
+            p99: percentile(99.0),
+            max: sorted.last().copied().unwrap_or(0.0),
+        }
+    }
+}
+
+// Résultats agrégés d'une configuration
+#[derive(Clone, Debug, Serialize)]
+pub struct ConfigurationReport {
+    pub strategy: &'static str,
+    pub explorers: usize,
+    pub extractors: usize,
+    pub dispatch: String,
+    pub runs: usize,
+    pub failures: usize,
+    pub failure_rate: f64,
+    pub ticks: Stats,
+    pub collected: Stats,
+    pub distance: Stats,
+    pub idle_ticks: Stats,
+    pub throughput: Stats,
+}
+
+impl ConfigurationReport {
+    fn new(configuration: Configuration, summaries: &[MissionSummary]) -> Self {
+        let stats = |metric: fn(&MissionSummary) -> usize| {
+            Stats::new(
+                &summaries
+                    .iter()
+                    .map(|s| metric(s) as f64)
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let failures = summaries
+            .iter()
+            .filter(|s| !s.outcome.is_some_and(|outcome| outcome.is_success()))
+            .count();
+        ConfigurationReport {
+            strategy: configuration.strategy.label(),
+            explorers: configuration.fleet.0,
+            extractors: configuration.fleet.1,
+            dispatch: format!("{:?}", configuration.dispatch_mode).to_lowercase(),
+            runs: summaries.len(),
+            failures,
+            failure_rate: failures as f64 / summaries.len().max(1) as f64,
+            ticks: stats(|s| s.ticks),
+            collected: stats(|s| s.collected),
+            distance: stats(|s| s.distance),
+            idle_ticks: stats(|s| s.idle_ticks),
+            throughput: Stats::new(
+                &summaries
+                    .iter()
+                    .map(|s| s.throughput as f64)
+                    .collect::<Vec<_>>(),
+            ),
+        }
+    }
+}
+
+impl Sweep {
+    fn configurations(&self) -> Vec<Configuration> {
+        let mut configurations = vec![];
+        for &strategy in &self.strategies {
+            for &fleet in &self.fleets {
+                for &dispatch_mode in &self.dispatch_modes {
+                    configurations.push(Configuration {
+                        strategy,
+                        fleet,
+                        dispatch_mode,
+                    });
+                }
+            }
+        }
+        configurations
+    }
+
+    fn run_mission(&self, configuration: Configuration, seed: u64) -> MissionSummary {
+        let config = MissionConfig {
+            explorers: configuration.fleet.0,
+            extractors: configuration.fleet.1,
+            strategy: configuration.strategy,
+            ..self.config.clone()
+        };
+        let (mut map, _) = Map::generate(seed, &self.settings, &config);
+        map.dispatch_mode = configuration.dispatch_mode;
+        map.verbose = false;
+        map.run_headless()
+    }
+
+    // Joue toutes les missions en parallèle ; le résultat ne dépend pas du nombre de threads
+    pub fn run(&self) -> Vec<ConfigurationReport> {
+        let configurations = self.configurations();
+        let seeds: Vec<u64> = self.seeds.clone().collect();
+        let jobs = configurations.len() * seeds.len();
+        let next = AtomicUsize::new(0);
+
+        let mut results: Vec<(usize, MissionSummary)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.threads.max(1))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = vec![];
+                        loop {
+                            let job = next.fetch_add(1, Ordering::Relaxed);
+                            if job >= jobs {
+                                break done;
+                            }
+                            let configuration = configurations[job / seeds.len()];
+                            let seed = seeds[job % seeds.len()];
+                            done.push((job, self.run_mission(configuration, seed)));
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("mission thread panicked"))
+                .collect()
+        });
+        results.sort_by_key(|&(job, _)| job);
+
+        let summaries: Vec<MissionSummary> = results.into_iter().map(|(_, s)| s).collect();
+        configurations
+            .iter()
+            .zip(summaries.chunks(seeds.len().max(1)))
+            .map(|(&configuration, summaries)| ConfigurationReport::new(configuration, summaries))
+            .collect()
+    }
+}
+
+pub fn to_csv(reports: &[ConfigurationReport]) -> String {
+    const METRICS: [&str; 5] = ["ticks", "collected", "distance", "idle_ticks", "throughput"];
+    const STATS: [&str; 7] = ["mean", "stddev", "min", "p50", "p90", "p99", "max"];
+
+    let mut out = String::from("strategy,explorers,extractors,dispatch,runs,failures,failure_rate");
+    for metric in METRICS {
+        for stat in STATS {
+            let _ = write!(out, ",{}_{}", metric, stat);
+        }
+    }
+    out.push('\n');
+
+    for report in reports {
+        let _ = write!(
+            out,
+            "{},{},{},{},{},{},{:.4}",
+            report.strategy,
+            report.explorers,
+            report.extractors,
+            report.dispatch,
+            report.runs,
+            report.failures,
+            report.failure_rate
+        );
+        for stats in [
+            &report.ticks,
+            &report.collected,
+            &report.distance,
+            &report.idle_ticks,
+            &report.throughput,
+        ] {
+            for value in [
+                stats.mean,
+                stats.stddev,
+                stats.min,
+                stats.p50,
+                stats.p90,
+                stats.p99,
+                stats.max,
+            ] {
+                let _ = write!(out, ",{:.2}", value);
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub fn to_json(reports: &[ConfigurationReport]) -> String {
+    serde_json::to_string_pretty(reports).expect("reports are always serializable") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_use_nearest_rank_percentiles_and_sample_stddev() {
+        let stats = Stats::new(&[7.0, 3.0, 10.0, 1.0, 5.0, 9.0, 2.0, 8.0, 4.0, 6.0]);
+        assert_eq!(stats.mean, 5.5);
+        // Somme des carrés des écarts 82.5, divisée par n - 1
+        assert!((stats.stddev - (82.5f64 / 9.0).sqrt()).abs() < 1e-12);
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.p50, 5.0);
+        assert_eq!(stats.p90, 9.0);
+        assert_eq!(stats.p99, 10.0);
+        assert_eq!(stats.max, 10.0);
+
+        let single = Stats::new(&[4.0]);
+        assert_eq!((single.stddev, single.p50, single.p99), (0.0, 4.0, 4.0));
+    }
+
+    #[test]
+    fn stats_of_no_values_are_zero() {
+        let stats = Stats::new(&[]);
+        for value in [
+            stats.mean,
+            stats.stddev,
+            stats.min,
+            stats.p50,
+            stats.p90,
+            stats.p99,
+            stats.max,
+        ] {
+            assert_eq!(value, 0.0);
+        }
+    }
+
+    #[test]
+    fn reports_do_not_depend_on_the_thread_count() {
+        let sweep = |threads| Sweep {
+            seeds: 1..=3,
+            strategies: ExplorerStrategy::ALL.to_vec(),
+            fleets: vec![(1, 1), (2, 2)],
+            dispatch_modes: vec![DispatchMode::Serialized, DispatchMode::Concurrent],
+            config: MissionConfig {
+                max_ticks: 300,
+                ..MissionConfig::default()
+            },
+            settings: GenerationSettings::default(),
+            threads,
+        };
+        let single = sweep(1).run();
+        let parallel = sweep(4).run();
+        assert_eq!(single.len(), 8);
+        assert_eq!(to_csv(&single), to_csv(&parallel));
+        assert_eq!(to_json(&single), to_json(&parallel));
+    }
+}
@@ -0,0 +1,523 @@
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Scope, AST};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::camera::RobotRef;
+use crate::map::{Grid, Map};
+use crate::robot::{RobotExplorer, RobotExtractor};
+use crate::station::ResourceKind;
+use crate::ELEVATION_SCALE;
+
+pub const DEFAULT_SCRIPT_BUDGET: u64 = 10_000;
+
+// Décision renvoyée par un script pour le tick courant
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Action {
+    // Une case dans la direction donnée, chaque composante entre -1 et 1
+    Move(i64, i64),
+    // Un pas le long du chemin A* vers une case
+    GoTo(i64, i64),
+    Harvest,
+    GoHome,
+    Wait,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::Move(dx, dy) => write!(f, "move {} {}", dx, dy),
+            Action::GoTo(x, y) => write!(f, "go_to {} {}", x, y),
+            Action::Harvest => write!(f, "harvest"),
+            Action::GoHome => write!(f, "go_home"),
+            Action::Wait => write!(f, "wait"),
+        }
+    }
+}
+
+impl Action {
+    // Inverse de l'affichage : `move 1 -1`, `go_to 3 4`, `harvest`...
+    pub fn parse(words: &[&str]) -> Option<Action> {
+        let number = |index: usize| words.get(index)?.parse().ok();
+        match words {
+            ["move", _, _] => Some(Action::Move(number(1)?, number(2)?)),
+            ["go_to", _, _] => Some(Action::GoTo(number(1)?, number(2)?)),
+            ["harvest"] => Some(Action::Harvest),
+            ["go_home"] => Some(Action::GoHome),
+            ["wait"] => Some(Action::Wait),
+            _ => None,
+        }
+    }
+}
+
+// Ce que la flotte sait de la carte au début du tick : seules les cases découvertes
+// sont renseignées, comme pour les robots eux-mêmes
+pub struct Knowledge {
+    size: usize,
+    known: Grid<bool>,
+    obstacles: Grid<bool>,
+    energy: Grid<bool>,
+    minerals: Grid<bool>,
+    explored: Grid<bool>,
+    elevation: Grid<f64>,
+    station: (usize, usize),
+    pending_sites: usize,
+}
+
+// Vue en lecture seule transmise aux scripts
+#[derive(Clone)]
+pub struct KnowledgeView(Arc<Knowledge>);
+
+impl KnowledgeView {
+    fn cell(&self, x: i64, y: i64) -> Option<(usize, usize)> {
+        let size = self.0.size as i64;
+        if (0..size).contains(&x) && (0..size).contains(&y) {
+            Some((x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+
+    fn known(&mut self, x: i64, y: i64) -> bool {
+        self.cell(x, y).is_some_and(|(x, y)| self.0.known[y][x])
+    }
+
+    // Les cases hors carte comptent comme des obstacles
+    fn obstacle(&mut self, x: i64, y: i64) -> bool {
+        self.cell(x, y)
+            .is_none_or(|(x, y)| self.0.known[y][x] && self.0.obstacles[y][x])
+    }
+
+    fn explored(&mut self, x: i64, y: i64) -> bool {
+        self.cell(x, y).is_some_and(|(x, y)| self.0.explored[y][x])
+    }
+
+    fn resource(&mut self, x: i64, y: i64) -> String {
+        let label = match self.cell(x, y) {
+            Some((x, y)) if !self.0.known[y][x] => "unknown",
+            Some((x, y)) if self.0.energy[y][x] => "energy",
+            Some((x, y)) if self.0.minerals[y][x] => "minerals",
+            _ => "none",
+        };
+        label.to_string()
+    }
+
+    // Altitude en mètres, 0 pour une case inconnue
+    fn elevation(&mut self, x: i64, y: i64) -> f64 {
+        self.cell(x, y)
+            .filter(|&(x, y)| self.0.known[y][x])
+            .map_or(0.0, |(x, y)| self.0.elevation[y][x] * ELEVATION_SCALE)
+    }
+}
+
+impl Map {
+    pub fn knowledge(&self) -> KnowledgeView {
+        let known = grid_map(&self.fog_of_war, |&fog| !fog);
+        KnowledgeView(Arc::new(Knowledge {
+            size: self.size,
+            obstacles: self.obstacles.clone(),
+            energy: self.seen_energy.clone(),
+            minerals: self.seen_minerals.clone(),
+            explored: self.explored.clone(),
+            elevation: self.elevation.clone(),
+            station: (self.station.x, self.station.y),
+            pending_sites: self.station.pending_sites.len(),
+            known,
+        }))
+    }
+}
+
+fn grid_map<T, U>(grid: &Grid<T>, f: impl Fn(&T) -> U + Copy) -> Grid<U> {
+    grid.iter().map(|row| row.iter().map(f).collect()).collect()
+}
+
+// Fonction de décision écrite en Rhai, compilée au chargement
+#[derive(Clone)]
+pub struct Brain {
+    pub path: PathBuf,
+    pub budget: u64,
+    // Empreinte du texte du script, pour reconnaître un script modifié depuis un enregistrement
+    pub source_hash: u64,
+    engine: Arc<Engine>,
+    ast: Arc<AST>,
+}
+
+impl fmt::Debug for Brain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Brain({}, budget {})", self.path.display(), self.budget)
+    }
+}
+
+impl PartialEq for Brain {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+            && self.budget == other.budget
+            && self.source_hash == other.source_hash
+    }
+}
+
+// Empreinte FNV-1a du texte d'un script
+fn source_hash(source: &str) -> u64 {
+    source.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// Moteur sans accès au système, limité à `budget` opérations par appel
+fn sandboxed_engine(budget: u64) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(budget)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(1_024)
+        .set_max_array_size(10_000)
+        .set_max_map_size(1_000)
+        .disable_symbol("eval")
+        .on_print(|text| eprintln!("[script] {}", text))
+        .on_debug(|text, _, _| eprintln!("[script] {}", text));
+
+    engine
+        .register_type_with_name::<Action>("Action")
+        .register_fn("move", |dx: i64, dy: i64| Action::Move(dx, dy))
+        .register_fn("go_to", |x: i64, y: i64| Action::GoTo(x, y))
+        .register_fn("harvest", || Action::Harvest)
+        .register_fn("go_home", || Action::GoHome)
+        .register_fn("wait", || Action::Wait)
+        .register_fn("to_string", |action: &mut Action| action.to_string());
+
+    engine
+        .register_type_with_name::<KnowledgeView>("View")
+        .register_get("size", |view: &mut KnowledgeView| view.0.size as i64)
+        .register_get("station", |view: &mut KnowledgeView| {
+            let (x, y) = view.0.station;
+            vec![Dynamic::from(x as i64), Dynamic::from(y as i64)]
+        })
+        .register_get("pending_sites", |view: &mut KnowledgeView| {
+            view.0.pending_sites as i64
+        })
+        .register_fn("is_known", KnowledgeView::known)
+        .register_fn("is_obstacle", KnowledgeView::obstacle)
+        .register_fn("is_explored", KnowledgeView::explored)
+        .register_fn("resource", KnowledgeView::resource)
+        .register_fn("elevation", KnowledgeView::elevation);
+    engine
+}
+
+impl Brain {
+    pub fn load(path: &Path, budget: u64) -> Result<Brain, String> {
+        let source =
+            fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        let engine = sandboxed_engine(budget);
+        let ast = engine
+            .compile(&source)
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == "decide" && f.params.len() == 2)
+        {
+            return Err(format!(
+                "{}: the script must define fn decide(robot, view)",
+                path.display()
+            ));
+        }
+        Ok(Brain {
+            path: path.to_path_buf(),
+            budget,
+            source_hash: source_hash(&source),
+            engine: Arc::new(engine),
+            ast: Arc::new(ast),
+        })
+    }
+
+    // Appelle `decide(robot, view)` avec la mémoire du robot comme `this`
+    fn decide(
+        &self,
+        memory: &mut Dynamic,
+        robot: rhai::Map,
+        view: &KnowledgeView,
+    ) -> Result<Action, String> {
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(memory);
+        let result: Dynamic = self
+            .engine
+            .call_fn_with_options(
+                options,
+                &mut Scope::new(),
+                &self.ast,
+                "decide",
+                (robot, view.clone()),
+            )
+            .map_err(|error| match *error {
+                EvalAltResult::ErrorTooManyOperations(_) => {
+                    format!("exceeded the budget of {} operations", self.budget)
+                }
+                error => error.to_string(),
+            })?;
+        let type_name = result.type_name();
+        result
+            .try_cast::<Action>()
+            .ok_or_else(|| format!("decide returned {} instead of an action", type_name))
+    }
+}
+
+// Cerveaux scriptés de la mission, par type de robot
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Scripts {
+    pub explorer: Option<Brain>,
+    pub extractor: Option<Brain>,
+}
+
+impl Scripts {
+    pub fn is_empty(&self) -> bool {
+        self.explorer.is_none() && self.extractor.is_none()
+    }
+}
+
+// Mémoire d'un robot scripté, conservée d'un tick à l'autre
+#[derive(Clone, Debug)]
+pub struct BrainState {
+    pub memory: Dynamic,
+    pub last_error: Option<String>,
+}
+
+impl Default for BrainState {
+    fn default() -> Self {
+        BrainState {
+            memory: Dynamic::from_map(rhai::Map::new()),
+            last_error: None,
+        }
+    }
+}
+
+fn cargo_label(cargo: Option<ResourceKind>) -> &'static str {
+    match cargo {
+        Some(ResourceKind::Energy) => "energy",
+        Some(ResourceKind::Minerals) => "minerals",
+        None => "",
+    }
+}
+
+fn cell_value(cell: Option<(usize, usize)>) -> Dynamic {
+    match cell {
+        Some((x, y)) => Dynamic::from_array(vec![Dynamic::from(x as i64), Dynamic::from(y as i64)]),
+        None => Dynamic::UNIT,
+    }
+}
+
+fn robot_value(
+    map: &Map,
+    kind: &str,
+    index: usize,
+    (x, y): (usize, usize),
+    battery: f32,
+) -> rhai::Map {
+    let mut robot = rhai::Map::new();
+    robot.insert("kind".into(), kind.into());
+    robot.insert("index".into(), (index as i64).into());
+    robot.insert("x".into(), (x as i64).into());
+    robot.insert("y".into(), (y as i64).into());
+    robot.insert("battery".into(), (battery as f64).into());
+    robot.insert(
+        "battery_capacity".into(),
+        (map.config.robots.battery_capacity as f64).into(),
+    );
+    robot.insert("tick".into(), (map.tick as i64).into());
+    robot.insert("daylight".into(), (map.daylight() as f64).into());
+    robot.insert(
+        "ticks_until_night".into(),
+        (map.config
+            .day
+            .ticks_until_night(map.tick)
+            .min(i64::MAX as usize) as i64)
+            .into(),
+    );
+    robot
+}
+
+// Origine de la décision d'un robot qui n'utilise pas le comportement intégré
+#[derive(Clone, Copy)]
+pub enum Pilot<'a> {
+    Script(&'a Brain, &'a KnowledgeView),
+    // Ordre reçu d'un agent externe pour ce tick
+    Order(Action),
+}
+
+impl Map {
+    // Un ordre externe prime sur le script, qui prime sur le comportement intégré
+    pub(crate) fn pilot<'a>(
+        &mut self,
+        robot: RobotRef,
+        brain: &'a Option<Brain>,
+        view: &'a Option<KnowledgeView>,
+    ) -> Option<Pilot<'a>> {
+        if let Some(index) = self.orders.iter().position(|&(r, _)| r == robot) {
+            return Some(Pilot::Order(self.orders.swap_remove(index).1));
+        }
+        match (brain, view) {
+            (Some(brain), Some(view)) => Some(Pilot::Script(brain, view)),
+            _ => None,
+        }
+    }
+
+    // Journalise une erreur de pilotage seulement quand elle change
+    fn report_error(&self, label: &str, state: &mut BrainState, error: String) {
+        if state.last_error.as_ref() != Some(&error) {
+            self.log(format_args!("{}: {}", label, error));
+            state.last_error = Some(error);
+        }
+    }
+
+    fn decision(
+        &self,
+        pilot: Pilot,
+        label: &str,
+        state: &mut BrainState,
+        robot: impl FnOnce() -> rhai::Map,
+    ) -> Action {
+        match pilot {
+            Pilot::Order(action) => action,
+            Pilot::Script(brain, view) => match brain.decide(&mut state.memory, robot(), view) {
+                Ok(action) => {
+                    state.last_error = None;
+                    action
+                }
+                Err(error) => {
+                    self.report_error(label, state, format!("script error: {}", error));
+                    Action::Wait
+                }
+            },
+        }
+    }
+
+    fn cell_at(&self, x: i64, y: i64) -> Option<(usize, usize)> {
+        let size = self.size as i64;
+        if (0..size).contains(&x) && (0..size).contains(&y) {
+            Some((x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+
+    // Case visée par un déplacement élémentaire, si elle est sur la carte
+    fn neighbor_cell(&self, (x, y): (usize, usize), dx: i64, dy: i64) -> Option<(usize, usize)> {
+        if !(-1..=1).contains(&dx) || !(-1..=1).contains(&dy) || (dx, dy) == (0, 0) {
+            return None;
+        }
+        self.cell_at(x as i64 + dx, y as i64 + dy)
+    }
+
+    pub(crate) fn directed_explorer(
+        &mut self,
+        pilot: Pilot,
+        index: usize,
+        explorer: &mut RobotExplorer,
+    ) {
+        // La recharge et le signalement des sites restent automatiques à la station
+        if self.recharge_explorer(explorer) {
+            return;
+        }
+        self.report_explorer_site(explorer);
+
+        let label = format!("Explorer {}", index);
+        let mut state = std::mem::take(&mut explorer.brain);
+        let action = self.decision(pilot, &label, &mut state, || {
+            let mut robot = robot_value(
+                self,
+                "explorer",
+                index,
+                (explorer.x, explorer.y),
+                explorer.battery,
+            );
+            robot.insert("health".into(), (explorer.health as f64).into());
+            robot.insert("resource".into(), cell_value(explorer.resource_position));
+            robot
+        });
+
+        let path = match action {
+            Action::Move(dx, dy) => self
+                .neighbor_cell((explorer.x, explorer.y), dx, dy)
+                .map(|next| vec![(explorer.x, explorer.y), next]),
+            Action::GoTo(x, y) => self
+                .cell_at(x, y)
+                .and_then(|goal| explorer.plan_to(self, goal)),
+            Action::GoHome => explorer.return_to_station(self),
+            Action::Harvest => {
+                self.report_error(&label, &mut state, "explorers cannot harvest".to_string());
+                None
+            }
+            Action::Wait => None,
+        };
+        explorer.brain = state;
+
+        if let Some(path) = path {
+            if let Some((x, y)) = self.step_along(
+                &path,
+                false,
+                &mut explorer.move_progress,
+                &mut explorer.battery,
+            ) {
+                explorer.visit(self, x, y);
+            }
+        }
+    }
+
+    pub(crate) fn directed_extractor(
+        &mut self,
+        pilot: Pilot,
+        index: usize,
+        extractor: &mut RobotExtractor,
+    ) {
+        // Recharge, attribution des sites et dépôt restent automatiques à la station
+        self.recharge_extractor(extractor);
+        self.assign_site(extractor);
+
+        let label = format!("Extractor {}", index);
+        let mut state = std::mem::take(&mut extractor.brain);
+        let action = self.decision(pilot, &label, &mut state, || {
+            let mut robot = robot_value(
+                self,
+                "extractor",
+                index,
+                (extractor.x, extractor.y),
+                extractor.battery,
+            );
+            robot.insert("health".into(), (extractor.health as f64).into());
+            robot.insert("cargo".into(), cargo_label(extractor.cargo).into());
+            robot.insert("target".into(), cell_value(extractor.target_position));
+            robot
+        });
+        extractor.brain = state;
+
+        let loaded = extractor.cargo.is_some();
+        let path = match action {
+            Action::Move(dx, dy) => self
+                .neighbor_cell((extractor.x, extractor.y), dx, dy)
+                .map(|next| vec![(extractor.x, extractor.y), next]),
+            Action::GoTo(x, y) => self
+                .cell_at(x, y)
+                .and_then(|(x, y)| extractor.move_towards(x, y, self)),
+            Action::GoHome => {
+                extractor.move_towards(extractor.station_x, extractor.station_y, self)
+            }
+            Action::Harvest => {
+                self.harvest(extractor);
+                None
+            }
+            Action::Wait => None,
+        };
+
+        if let Some(path) = path {
+            if let Some((x, y)) = self.step_along(
+                &path,
+                loaded,
+                &mut extractor.move_progress,
+                &mut extractor.battery,
+            ) {
+                extractor.x = x;
+                extractor.y = y;
+                self.unload(extractor);
+            }
+        }
+    }
+}
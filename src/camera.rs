@@ -0,0 +1,250 @@
+use ggez::graphics::{self, Color, DrawParam, MeshBuilder, Rect};
+use ggez::{Context, GameResult};
+use std::fmt;
+
+use crate::map::Map;
+use crate::CELL_SIZE;
+use crate::{
+    DEFAULT_COLOR, ENERGY_COLOR, FOG_COLOR, MINERALS_COLOR, OBSTACLE_COLOR, ROBOT_EXPLORER_COLOR,
+    ROBOT_EXTRACTOR_COLOR, STATION_COLOR,
+};
+
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 8.0;
+const MINIMAP_SIZE: f32 = 120.0;
+// Nombre maximal de cases échantillonnées par côté sur la minicarte
+const MINIMAP_SAMPLES: usize = 64;
+
+// Robot désigné par son rôle et son indice dans la flotte
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RobotRef {
+    Explorer(usize),
+    Extractor(usize),
+}
+
+impl fmt::Display for RobotRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RobotRef::Explorer(index) => write!(f, "Explorer {}", index),
+            RobotRef::Extractor(index) => write!(f, "Extractor {}", index),
+        }
+    }
+}
+
+impl RobotRef {
+    // Rang du robot dans la liste explorateurs puis extracteurs
+    pub fn slot(self, map: &Map) -> usize {
+        match self {
+            RobotRef::Explorer(index) => index,
+            RobotRef::Extractor(index) => map.robot_explorers.len() + index,
+        }
+    }
+
+    // Robot suivant dans la flotte, explorateurs puis extracteurs
+    pub fn next(current: Option<RobotRef>, map: &Map) -> Option<RobotRef> {
+        let robots: Vec<RobotRef> = (0..map.robot_explorers.len())
+            .map(RobotRef::Explorer)
+            .chain((0..map.robot_extractors.len()).map(RobotRef::Extractor))
+            .collect();
+        let index = match current.and_then(|robot| robots.iter().position(|&r| r == robot)) {
+            Some(index) => (index + 1) % robots.len(),
+            None => 0,
+        };
+        robots.get(index).copied()
+    }
+}
+
+// Caméra : centre de la vue en pixels du monde, zoom et robot suivi
+pub struct Camera {
+    pub x: f32,
+    pub y: f32,
+    pub zoom: f32,
+    pub selected: Option<RobotRef>,
+    pub follow: bool,
+    pub dragging: bool,
+    pub map_size: usize,
+}
+
+impl Camera {
+    pub fn new(map_size: usize) -> Self {
+        Camera {
+            x: map_size as f32 * CELL_SIZE / 2.0,
+            y: map_size as f32 * CELL_SIZE / 2.0,
+            zoom: 1.0,
+            selected: None,
+            follow: false,
+            dragging: false,
+            map_size,
+        }
+    }
+
+    pub fn focus_cell(&mut self, x: usize, y: usize) {
+        self.focus(x as f32, y as f32);
+    }
+
+    // Centre la vue sur une position en cases, éventuellement fractionnaire
+    pub fn focus(&mut self, x: f32, y: f32) {
+        self.x = (x + 0.5) * CELL_SIZE;
+        self.y = (y + 0.5) * CELL_SIZE;
+    }
+
+    // Déplace la vue d'un décalage exprimé en pixels d'écran
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.x -= dx / self.zoom;
+        self.y -= dy / self.zoom;
+        self.follow = false;
+    }
+
+    // Zoome en gardant fixe le point du monde situé sous le curseur
+    pub fn zoom_at(&mut self, factor: f32, screen: Rect, anchor: (f32, f32)) {
+        let before = self.screen_to_world(screen, anchor);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let after = self.screen_to_world(screen, anchor);
+        self.x += before.0 - after.0;
+        self.y += before.1 - after.1;
+    }
+
+    // Suit le robot sélectionné à sa position interpolée
+    pub fn update(&mut self, map: &Map, positions: &[(f32, f32)]) {
+        if self.follow {
+            if let Some(&(x, y)) = self
+                .selected
+                .and_then(|robot| positions.get(robot.slot(map)))
+            {
+                self.focus(x, y);
+            }
+        }
+    }
+
+    pub fn screen_to_world(&self, screen: Rect, (sx, sy): (f32, f32)) -> (f32, f32) {
+        (
+            (sx - screen.w / 2.0) / self.zoom + self.x,
+            (sy - screen.h / 2.0) / self.zoom + self.y,
+        )
+    }
+
+    pub fn world_to_screen(&self, screen: Rect, (wx, wy): (f32, f32)) -> (f32, f32) {
+        (
+            (wx - self.x) * self.zoom + screen.w / 2.0,
+            (wy - self.y) * self.zoom + screen.h / 2.0,
+        )
+    }
+
+    // Case de la carte sous un point de l'écran
+    pub fn cell_at(&self, screen: Rect, position: (f32, f32)) -> Option<(usize, usize)> {
+        let (wx, wy) = self.screen_to_world(screen, position);
+        let (x, y) = ((wx / CELL_SIZE).floor(), (wy / CELL_SIZE).floor());
+        let size = self.map_size as f32;
+        if x < 0.0 || y < 0.0 || x >= size || y >= size {
+            return None;
+        }
+        Some((x as usize, y as usize))
+    }
+
+    // Zone du monde visible à l'écran, en pixels du monde
+    pub fn visible_area(&self, screen: Rect) -> Rect {
+        let (x, y) = self.screen_to_world(screen, (0.0, 0.0));
+        Rect::new(x, y, screen.w / self.zoom, screen.h / self.zoom)
+    }
+
+    pub fn transform(&self, screen: Rect) -> DrawParam {
+        DrawParam::default()
+            .dest([
+                screen.w / 2.0 - self.x * self.zoom,
+                screen.h / 2.0 - self.y * self.zoom,
+            ])
+            .scale([self.zoom, self.zoom])
+    }
+
+    pub fn push(&self, ctx: &mut Context) -> GameResult {
+        let screen = graphics::screen_coordinates(ctx);
+        graphics::push_transform(ctx, Some(self.transform(screen).to_matrix()));
+        graphics::apply_transformations(ctx)
+    }
+
+    pub fn pop(&self, ctx: &mut Context) -> GameResult {
+        graphics::pop_transform(ctx);
+        graphics::apply_transformations(ctx)
+    }
+}
+
+// Minicarte dans le coin supérieur droit, avec le cadre de la vue courante
+pub fn draw_minimap(ctx: &mut Context, map: &Map, camera: &Camera) -> GameResult {
+    let screen = graphics::screen_coordinates(ctx);
+    let origin = (screen.w - MINIMAP_SIZE - 8.0, 8.0);
+    let step = map.size.div_ceil(MINIMAP_SAMPLES);
+    let scale = MINIMAP_SIZE / (map.size as f32 * CELL_SIZE);
+    let cell = step as f32 * CELL_SIZE * scale;
+
+    let mut mesh = MeshBuilder::new();
+    for y in (0..map.size).step_by(step) {
+        for x in (0..map.size).step_by(step) {
+            let color = if map.fog_of_war[y][x] {
+                FOG_COLOR
+            } else if map.obstacles[y][x] {
+                OBSTACLE_COLOR
+            } else if map.energy[y][x] {
+                ENERGY_COLOR
+            } else if map.minerals[y][x] {
+                MINERALS_COLOR
+            } else {
+                DEFAULT_COLOR
+            };
+            let rect = Rect::new(
+                origin.0 + x as f32 * CELL_SIZE * scale,
+                origin.1 + y as f32 * CELL_SIZE * scale,
+                cell,
+                cell,
+            );
+            mesh.rectangle(graphics::DrawMode::fill(), rect, color);
+        }
+    }
+
+    let robots = map
+        .robot_explorers
+        .iter()
+        .map(|e| (e.x, e.y, ROBOT_EXPLORER_COLOR))
+        .chain(
+            map.robot_extractors
+                .iter()
+                .map(|e| (e.x, e.y, ROBOT_EXTRACTOR_COLOR)),
+        )
+        .chain(std::iter::once((
+            map.station.x,
+            map.station.y,
+            STATION_COLOR,
+        )));
+    for (x, y, color) in robots {
+        mesh.circle(
+            graphics::DrawMode::fill(),
+            [
+                origin.0 + (x as f32 + 0.5) * CELL_SIZE * scale,
+                origin.1 + (y as f32 + 0.5) * CELL_SIZE * scale,
+            ],
+            2.0,
+            0.5,
+            color,
+        );
+    }
+
+    let view = camera.visible_area(screen);
+    let frame = Rect::new(
+        origin.0 + view.x * scale,
+        origin.1 + view.y * scale,
+        view.w * scale,
+        view.h * scale,
+    );
+    mesh.rectangle(
+        graphics::DrawMode::stroke(1.0),
+        frame,
+        Color::new(1.0, 0.0, 0.0, 1.0),
+    );
+    mesh.rectangle(
+        graphics::DrawMode::stroke(1.0),
+        Rect::new(origin.0, origin.1, MINIMAP_SIZE, MINIMAP_SIZE),
+        graphics::BLACK,
+    );
+
+    let mesh = mesh.build(ctx)?;
+    graphics::draw(ctx, &mesh, DrawParam::default())
+}
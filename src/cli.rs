@@ -0,0 +1,384 @@
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::agent::{AgentEndpoint, AgentOptions, DEFAULT_AGENT_TIMEOUT};
+use crate::brain::{Brain, DEFAULT_SCRIPT_BUDGET};
+use crate::env::{Channel, ObservationEncoding, RewardShaping};
+use crate::map::DispatchMode;
+use crate::mission::{ExplorerStrategy, MissionConfig, Victory};
+use crate::scenario::Scenario;
+use crate::terrain::GenerationSettings;
+
+pub const USAGE: &str = "\
+Usage: cosmobots [COMMAND] [OPTIONS] [layer.field=value ...]
+
+Commands:
+  run              open the simulation window (default)
+  sim              run missions headless and print their summaries
+  gen              generate a map and export its text preview
+  replay <FILE>    play a recorded mission back in the window
+  bench            measure generation and simulation throughput
+  batch            run a parameter sweep in parallel and report statistics
+  agent            run the stand-in agent client, for testing the agent protocol
+  gym              serve a reset/step learning environment over stdin/stdout
+
+Options:
+  --scenario <FILE>      load the mission from a scenario file, other options override it
+  --seed <N>             map seed (random by default)
+  --size <N>             map side in cells
+  --explorers <N>        number of explorers
+  --extractors <N>       number of extractors
+  --strategy <NAME>      explorer strategy, random or frontier
+  --max-ticks <N>        tick budget of a mission
+  --dispatch <MODE>      serialized or concurrent
+  --day-length <N>       ticks per day/night cycle, 0 for permanent daylight
+  --breakdown-rate <P>   chance per tick that a robot away from the station breaks down
+  --storm-rate <P>       chance per tick of a random dust storm
+  --rockfall-rate <P>    chance per tick of a random rockfall
+  --hazard-rate <P>      chance per tick of a random hazardous zone
+  --regrow-ticks <N>     ticks before a harvested deposit grows back, 0 for never
+  --spawn-rate <P>       chance per tick that a new deposit appears
+  --endless              run as a colony until the tick budget instead of until resources run out
+  --explorer-script <FILE>   drive the explorers with a Rhai script
+  --extractor-script <FILE>  drive the extractors with a Rhai script
+  --script-budget <N>    script operations allowed per robot and tick (default 10000)
+  --agent-listen <ADDR>  let an external agent connecting to ADDR drive the robots (run, sim)
+  --agent-command <CMD>  start CMD as the agent and talk to it over its stdin/stdout (run, sim)
+  --agent-timeout <MS>   wait at most MS per tick for the agent, default 100
+  --lockstep             wait for every agent reply instead of timing out
+  --connect <ADDR>       connect to a listening simulation instead of using stdin/stdout (agent)
+  --delay <MS>           wait before each reply, to exercise the timeout (agent)
+  --patch-radius <N>     cells observed on each side of a robot, default 3 (gym)
+  --channels <LIST>      observed layers among fog,obstacle,resource,explored,elevation (gym)
+  --reward-discovery <X> reward per newly discovered cell, default 0.1 (gym)
+  --reward-delivery <X>  reward per resource delivered, default 1 (gym)
+  --step-penalty <X>     penalty per tick, default 0.01 (gym)
+  --missions <N>         missions to run (sim, bench, batch)
+  --record <FILE>        record the mission to a replay file (run, sim)
+  --metrics <FILE>       dump the mission metrics as JSON (sim)
+  --output <FILE>        write the map (gen) or the summaries (sim, bench, batch) to a file
+  --compare              run each mission in both dispatch modes (sim, batch)
+  --validate             re-simulate the replay headless instead of playing it (replay)
+  --quiet                hide the mission event log (sim)
+  --seeds <A..B>         seeds A to B inclusive, instead of --seed and --missions (batch)
+  --strategies <LIST>    comma-separated explorer strategies to sweep (batch)
+  --fleets <LIST>        comma-separated fleets as EXPLORERSxEXTRACTORS, e.g. 2x2,4x2 (batch)
+  --threads <N>          worker threads, one per CPU core by default (batch)
+  --format <FORMAT>      csv or json, guessed from the --output extension by default (batch)
+  -h, --help             show this help
+
+Generation settings such as elevation.frequency=0.2 or energy_threshold=0.9
+can be overridden with layer.field=value arguments.";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Run,
+    Sim,
+    Gen,
+    Replay(PathBuf),
+    Bench,
+    Batch,
+    Agent,
+    Gym,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+// Ligne de commande analysée
+#[derive(Clone, Debug)]
+pub struct Cli {
+    pub command: Command,
+    // Scénario chargé, avant application des autres options
+    pub scenario: Option<Scenario>,
+    pub seed: Option<u64>,
+    pub config: MissionConfig,
+    pub settings: GenerationSettings,
+    // Paramètres de génération surchargés, conservés pour les relectures
+    pub overrides: Vec<(String, String)>,
+    pub dispatch_mode: DispatchMode,
+    pub missions: usize,
+    pub record: Option<PathBuf>,
+    pub metrics: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub compare: bool,
+    pub validate: bool,
+    pub quiet: bool,
+    pub seeds: Option<RangeInclusive<u64>>,
+    pub strategies: Vec<ExplorerStrategy>,
+    pub fleets: Vec<(usize, usize)>,
+    pub threads: Option<usize>,
+    pub format: Option<ReportFormat>,
+    pub agent: Option<AgentOptions>,
+    // Options du client de démonstration
+    pub connect: Option<String>,
+    pub delay: Duration,
+    pub encoding: ObservationEncoding,
+    pub rewards: RewardShaping,
+    pub help: bool,
+}
+
+// Scripts demandés sur la ligne de commande, chargés une fois toutes les options lues
+#[derive(Default)]
+struct ScriptOptions {
+    explorer: Option<PathBuf>,
+    extractor: Option<PathBuf>,
+    budget: Option<u64>,
+}
+
+impl Cli {
+    pub fn parse(args: &[String]) -> Result<Cli, String> {
+        let mut cli = Cli {
+            command: Command::Run,
+            scenario: None,
+            seed: None,
+            config: MissionConfig::default(),
+            settings: GenerationSettings::default(),
+            overrides: vec![],
+            dispatch_mode: DispatchMode::Concurrent,
+            missions: 1,
+            record: None,
+            metrics: None,
+            output: None,
+            compare: false,
+            validate: false,
+            quiet: false,
+            seeds: None,
+            strategies: vec![],
+            fleets: vec![],
+            threads: None,
+            format: None,
+            agent: None,
+            connect: None,
+            delay: Duration::ZERO,
+            encoding: ObservationEncoding::default(),
+            rewards: RewardShaping::default(),
+            help: false,
+        };
+
+        // Le scénario est appliqué en premier pour que les options le surchargent
+        if let Some(index) = args.iter().position(|arg| arg == "--scenario") {
+            let path = args.get(index + 1).ok_or("missing value for --scenario")?;
+            let scenario = Scenario::load(Path::new(path))?;
+            cli.seed = scenario.seed;
+            cli.config = scenario.config.clone();
+            cli.settings = scenario.settings.clone();
+            cli.overrides = scenario.overrides.clone();
+            cli.dispatch_mode = scenario.dispatch_mode.unwrap_or(cli.dispatch_mode);
+            cli.scenario = Some(scenario);
+        }
+
+        let mut args = args.iter();
+        let mut command = None;
+        let mut scripts = ScriptOptions::default();
+        let mut endpoint = None;
+        let mut timeout = Some(DEFAULT_AGENT_TIMEOUT);
+        let mut agent_timing = false;
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| format!("missing value for {}", name))
+            };
+            let number = |name: &str, text: String| {
+                text.parse::<usize>()
+                    .map_err(|_| format!("invalid value '{}' for {}", text, name))
+            };
+            let real = |name: &str, text: String| {
+                text.parse::<f32>()
+                    .ok()
+                    .filter(|value| value.is_finite())
+                    .ok_or_else(|| format!("invalid value '{}' for {}", text, name))
+            };
+            let rate = |name: &str, text: String| {
+                text.parse::<f64>()
+                    .ok()
+                    .filter(|value| (0.0..=1.0).contains(value))
+                    .ok_or_else(|| {
+                        format!(
+                            "invalid value '{}' for {}, expected a probability",
+                            text, name
+                        )
+                    })
+            };
+
+            match arg.as_str() {
+                "-h" | "--help" => cli.help = true,
+                "--scenario" => {
+                    value(arg)?;
+                }
+                "--seed" => {
+                    let text = value(arg)?;
+                    cli.seed = Some(
+                        text.parse()
+                            .map_err(|_| format!("invalid value '{}' for --seed", text))?,
+                    );
+                }
+                "--size" => cli.config.map_size = number(arg, value(arg)?)?,
+                "--explorers" => cli.config.explorers = number(arg, value(arg)?)?,
+                "--extractors" => cli.config.extractors = number(arg, value(arg)?)?,
+                "--strategy" => cli.config.strategy = ExplorerStrategy::parse(&value(arg)?)?,
+                "--max-ticks" => cli.config.max_ticks = number(arg, value(arg)?)?,
+                "--missions" => cli.missions = number(arg, value(arg)?)?,
+                "--dispatch" => {
+                    cli.dispatch_mode = match value(arg)?.as_str() {
+                        "serialized" => DispatchMode::Serialized,
+                        "concurrent" => DispatchMode::Concurrent,
+                        other => return Err(format!("unknown dispatch mode '{}'", other)),
+                    }
+                }
+                "--day-length" => cli.config.day.length = number(arg, value(arg)?)?,
+                "--breakdown-rate" => cli.config.robots.breakdown_rate = rate(arg, value(arg)?)?,
+                "--storm-rate" => cli.config.hazards.storm_rate = rate(arg, value(arg)?)?,
+                "--rockfall-rate" => cli.config.hazards.rockfall_rate = rate(arg, value(arg)?)?,
+                "--hazard-rate" => cli.config.hazards.hazard_rate = rate(arg, value(arg)?)?,
+                "--regrow-ticks" => cli.config.growth.regrow_ticks = number(arg, value(arg)?)?,
+                "--spawn-rate" => cli.config.growth.spawn_rate = rate(arg, value(arg)?)?,
+                "--endless" => cli.config.victory = Victory::Endless,
+                "--explorer-script" => scripts.explorer = Some(PathBuf::from(value(arg)?)),
+                "--extractor-script" => scripts.extractor = Some(PathBuf::from(value(arg)?)),
+                "--script-budget" => {
+                    let budget = number(arg, value(arg)?)?;
+                    if budget == 0 {
+                        return Err("--script-budget must be positive".to_string());
+                    }
+                    scripts.budget = Some(budget as u64);
+                }
+                "--agent-listen" => endpoint = Some(AgentEndpoint::Listen(value(arg)?)),
+                "--agent-command" => endpoint = Some(AgentEndpoint::Command(value(arg)?)),
+                "--agent-timeout" => {
+                    let millis = number(arg, value(arg)?)?;
+                    timeout = Some(Duration::from_millis(millis as u64));
+                    agent_timing = true;
+                }
+                "--lockstep" => {
+                    timeout = None;
+                    agent_timing = true;
+                }
+                "--connect" => cli.connect = Some(value(arg)?),
+                "--delay" => cli.delay = Duration::from_millis(number(arg, value(arg)?)? as u64),
+                "--patch-radius" => cli.encoding.radius = number(arg, value(arg)?)?,
+                "--channels" => {
+                    cli.encoding.channels = value(arg)?
+                        .split(',')
+                        .map(Channel::parse)
+                        .collect::<Result<_, _>>()?
+                }
+                "--reward-discovery" => cli.rewards.discovery = real(arg, value(arg)?)?,
+                "--reward-delivery" => cli.rewards.delivery = real(arg, value(arg)?)?,
+                "--step-penalty" => cli.rewards.step_penalty = real(arg, value(arg)?)?,
+                "--record" => cli.record = Some(PathBuf::from(value(arg)?)),
+                "--metrics" => cli.metrics = Some(PathBuf::from(value(arg)?)),
+                "--output" => cli.output = Some(PathBuf::from(value(arg)?)),
+                "--compare" => cli.compare = true,
+                "--validate" => cli.validate = true,
+                "--quiet" => cli.quiet = true,
+                "--seeds" => {
+                    let text = value(arg)?;
+                    let range = text
+                        .split_once("..")
+                        .and_then(|(a, b)| Some((a.parse().ok()?, b.parse().ok()?)))
+                        .filter(|(a, b)| a <= b)
+                        .ok_or_else(|| format!("invalid seed range '{}', expected A..B", text))?;
+                    cli.seeds = Some(range.0..=range.1);
+                }
+                "--strategies" => {
+                    cli.strategies = value(arg)?
+                        .split(',')
+                        .map(ExplorerStrategy::parse)
+                        .collect::<Result<_, _>>()?
+                }
+                "--fleets" => {
+                    let text = value(arg)?;
+                    cli.fleets = text
+                        .split(',')
+                        .map(|fleet| {
+                            fleet
+                                .split_once('x')
+                                .and_then(|(e, x)| Some((e.parse().ok()?, x.parse().ok()?)))
+                                .filter(|&(e, x)| e > 0 && x > 0)
+                                .ok_or_else(|| {
+                                    format!("invalid fleet '{}', expected e.g. 2x2", fleet)
+                                })
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                "--threads" => cli.threads = Some(number(arg, value(arg)?)?),
+                "--format" => {
+                    cli.format = match value(arg)?.as_str() {
+                        "csv" => Some(ReportFormat::Csv),
+                        "json" => Some(ReportFormat::Json),
+                        other => return Err(format!("unknown report format '{}'", other)),
+                    }
+                }
+                _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+                _ if arg.contains('=') => {
+                    let (key, value) = arg.split_once('=').unwrap();
+                    cli.settings.set(key, value)?;
+                    cli.overrides.push((key.to_string(), value.to_string()));
+                }
+                _ if command.is_none() => {
+                    command = Some(match arg.as_str() {
+                        "run" => Command::Run,
+                        "sim" => Command::Sim,
+                        "gen" => Command::Gen,
+                        "bench" => Command::Bench,
+                        "batch" => Command::Batch,
+                        "agent" => Command::Agent,
+                        "gym" => Command::Gym,
+                        "replay" => Command::Replay(PathBuf::from(
+                            args.next().ok_or("missing replay file")?,
+                        )),
+                        other => return Err(format!("unknown command '{}'", other)),
+                    });
+                }
+                _ => return Err(format!("unexpected argument '{}'", arg)),
+            }
+        }
+        if let Some(command) = command {
+            cli.command = command;
+        }
+
+        match endpoint {
+            Some(endpoint) => cli.agent = Some(AgentOptions { endpoint, timeout }),
+            None if agent_timing => {
+                return Err(
+                    "--agent-timeout and --lockstep need --agent-listen or --agent-command"
+                        .to_string(),
+                )
+            }
+            None => {}
+        }
+
+        // Un script du scénario est rechargé si son chemin ou son budget change
+        for (slot, path) in [
+            (&mut cli.config.scripts.explorer, scripts.explorer),
+            (&mut cli.config.scripts.extractor, scripts.extractor),
+        ] {
+            let current = slot
+                .as_ref()
+                .map(|brain| (brain.path.clone(), brain.budget));
+            let path = path.or_else(|| current.as_ref().map(|(path, _)| path.clone()));
+            if let Some(path) = path {
+                let budget = scripts
+                    .budget
+                    .or(current.as_ref().map(|&(_, budget)| budget))
+                    .unwrap_or(DEFAULT_SCRIPT_BUDGET);
+                if current != Some((path.clone(), budget)) {
+                    *slot = Some(Brain::load(&path, budget)?);
+                }
+            }
+        }
+
+        cli.config.validate()?;
+        cli.settings.validate()?;
+        if cli.missions == 0 {
+            return Err("at least one mission is required".to_string());
+        }
+        Ok(cli)
+    }
+}
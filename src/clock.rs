@@ -0,0 +1,168 @@
+use std::time::{Duration, Instant};
+
+use crate::agent::AgentLink;
+use crate::map::Map;
+use crate::replay::{Playback, Recorder};
+
+// Vitesses de simulation disponibles, en multiples d'un tick par seconde
+const SPEEDS: [f32; 10] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 100.0];
+const DEFAULT_SPEED: usize = 2;
+const TICK_DURATION: Duration = Duration::from_secs(1);
+// Garde-fous pour qu'une image lente ne déclenche pas une avalanche de ticks
+const MAX_TICKS_PER_FRAME: usize = 1_000;
+const FRAME_BUDGET: Duration = Duration::from_millis(12);
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Speed {
+    Scaled(usize),
+    // Autant de ticks que le budget d'une image le permet
+    Unlimited,
+}
+
+// Horloge de la simulation : pause, pas à pas, vitesse et interpolation du rendu
+pub struct SimClock {
+    pub paused: bool,
+    pub speed: Speed,
+    // Positions des robots avant le dernier tick, explorateurs puis extracteurs
+    pub previous: Vec<(usize, usize)>,
+    pub recorder: Option<Recorder>,
+    pub playback: Option<Playback>,
+    // Agent externe consulté avant chaque tick joué en direct
+    pub agent: Option<AgentLink>,
+}
+
+fn robot_positions(map: &Map) -> Vec<(usize, usize)> {
+    map.robot_explorers
+        .iter()
+        .map(|e| (e.x, e.y))
+        .chain(map.robot_extractors.iter().map(|e| (e.x, e.y)))
+        .collect()
+}
+
+impl SimClock {
+    pub fn new(map: &Map) -> Self {
+        SimClock {
+            paused: false,
+            speed: Speed::Scaled(DEFAULT_SPEED),
+            previous: robot_positions(map),
+            recorder: None,
+            playback: None,
+            agent: None,
+        }
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = match self.speed {
+            Speed::Scaled(index) if index + 1 < SPEEDS.len() => Speed::Scaled(index + 1),
+            _ => Speed::Unlimited,
+        };
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = match self.speed {
+            Speed::Scaled(index) => Speed::Scaled(index.saturating_sub(1)),
+            Speed::Unlimited => Speed::Scaled(SPEEDS.len() - 1),
+        };
+    }
+
+    pub fn label(&self) -> String {
+        let speed = match self.speed {
+            Speed::Scaled(index) => format!("{}x", SPEEDS[index]),
+            Speed::Unlimited => "max".to_string(),
+        };
+        let speed = if self.paused {
+            format!("{} (paused)", speed)
+        } else {
+            speed
+        };
+        match &self.playback {
+            Some(playback) => format!("{}, replay of {} ticks", speed, playback.len()),
+            None => speed,
+        }
+    }
+
+    fn tick(&mut self, map: &mut Map) {
+        self.previous = robot_positions(map);
+        if let Some(playback) = &mut self.playback {
+            playback.tick(map);
+            return;
+        }
+        if let Some(agent) = &mut self.agent {
+            agent.exchange(map);
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.tick(map);
+        } else {
+            map.update_robot();
+        }
+    }
+
+    // Déplacement dans une relecture, vers l'avant ou vers l'arrière
+    pub fn seek(&mut self, map: &mut Map, tick: usize) {
+        if let Some(playback) = &mut self.playback {
+            playback.seek(map, tick);
+            self.previous = robot_positions(map);
+            map.update_timer = TICK_DURATION;
+        }
+    }
+
+    // Joue un seul tick et affiche directement son résultat
+    pub fn step(&mut self, map: &mut Map) {
+        self.tick(map);
+        self.previous = robot_positions(map);
+        map.update_timer = TICK_DURATION;
+    }
+
+    // Fait avancer la simulation du temps réel écoulé ; `update_timer` contient
+    // le temps simulé restant avant le prochain tick
+    pub fn advance(&mut self, map: &mut Map, elapsed: Duration) {
+        if self.paused || map.game_over {
+            return;
+        }
+
+        match self.speed {
+            Speed::Unlimited => {
+                let start = Instant::now();
+                while !map.game_over && start.elapsed() < FRAME_BUDGET {
+                    self.tick(map);
+                }
+                map.update_timer = Duration::ZERO;
+            }
+            Speed::Scaled(index) => {
+                let mut budget = elapsed.mul_f32(SPEEDS[index]);
+                let mut ticks = 0;
+                while budget >= map.update_timer && !map.game_over {
+                    budget -= map.update_timer;
+                    self.tick(map);
+                    map.update_timer = TICK_DURATION;
+                    ticks += 1;
+                    if ticks >= MAX_TICKS_PER_FRAME {
+                        budget = Duration::ZERO;
+                    }
+                }
+                map.update_timer = map.update_timer.saturating_sub(budget);
+            }
+        }
+    }
+
+    // Avancement entre le tick précédent (0) et le tick courant (1)
+    pub fn alpha(&self, map: &Map) -> f32 {
+        1.0 - (map.update_timer.as_secs_f32() / TICK_DURATION.as_secs_f32()).clamp(0.0, 1.0)
+    }
+
+    // Positions des robots interpolées entre deux ticks, en cases
+    pub fn interpolated_positions(&self, map: &Map) -> Vec<(f32, f32)> {
+        let alpha = self.alpha(map);
+        robot_positions(map)
+            .into_iter()
+            .enumerate()
+            .map(|(index, (x, y))| {
+                let (px, py) = self.previous.get(index).copied().unwrap_or((x, y));
+                (
+                    px as f32 + (x as f32 - px as f32) * alpha,
+                    py as f32 + (y as f32 - py as f32) * alpha,
+                )
+            })
+            .collect()
+    }
+}
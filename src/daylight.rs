@@ -0,0 +1,127 @@
+use crate::map::Map;
+use crate::RobotExplorer;
+
+// Part de la journée occupée par chaque crépuscule
+const TWILIGHT: f32 = 0.05;
+// Ticks prévus par case pour rentrer à la station avant la nuit
+const RETURN_TICKS_PER_CELL: usize = 3;
+
+// Cycle jour/nuit de la planète, désactivé lorsque `length` vaut 0
+#[derive(Clone, Debug, PartialEq)]
+pub struct DayCycle {
+    // Durée d'une journée complète, en ticks
+    pub length: usize,
+    // Part de la journée plongée dans la nuit
+    pub night: f32,
+    // Portée des capteurs la nuit, en cases
+    pub night_sensor_range: usize,
+    // Part de la recharge solaire conservée la nuit
+    pub night_recharge: f32,
+}
+
+impl Default for DayCycle {
+    fn default() -> Self {
+        DayCycle {
+            length: 0,
+            night: 0.4,
+            night_sensor_range: 0,
+            night_recharge: 0.25,
+        }
+    }
+}
+
+impl DayCycle {
+    pub fn is_enabled(&self) -> bool {
+        self.length > 0
+    }
+
+    pub fn validate(&self, sensor_range: usize) -> Vec<String> {
+        let mut errors = vec![];
+        if !self.is_enabled() {
+            return errors;
+        }
+        if !(0.0..1.0).contains(&self.night) {
+            errors.push(format!(
+                "night must be at least 0 and below 1, got {}",
+                self.night
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.night_recharge) {
+            errors.push(format!(
+                "night_recharge must be between 0 and 1, got {}",
+                self.night_recharge
+            ));
+        }
+        if self.night_sensor_range > sensor_range {
+            errors.push(format!(
+                "night_sensor_range must not exceed sensor_range ({}), got {}",
+                sensor_range, self.night_sensor_range
+            ));
+        }
+        errors
+    }
+
+    // Avancement dans la journée entre 0 et 1 ; la mission commence en plein jour
+    fn phase(&self, time: f32) -> f32 {
+        (time / self.length as f32 + TWILIGHT).rem_euclid(1.0)
+    }
+
+    // Luminosité entre 0 (nuit) et 1 (jour), avec aube et crépuscule progressifs
+    pub fn daylight(&self, time: f32) -> f32 {
+        if !self.is_enabled() {
+            return 1.0;
+        }
+        let phase = self.phase(time);
+        let dusk = 1.0 - self.night;
+        if phase >= dusk {
+            return 0.0;
+        }
+        (phase / TWILIGHT).min((dusk - phase) / TWILIGHT).min(1.0)
+    }
+
+    // Ticks restants avant le milieu du crépuscule, 0 la nuit
+    pub fn ticks_until_night(&self, tick: usize) -> usize {
+        if !self.is_enabled() {
+            return usize::MAX;
+        }
+        let nightfall = 1.0 - self.night - TWILIGHT / 2.0;
+        let phase = self.phase(tick as f32);
+        if self.daylight(tick as f32) < 0.5 || phase >= nightfall {
+            0
+        } else {
+            ((nightfall - phase) * self.length as f32) as usize
+        }
+    }
+}
+
+impl Map {
+    pub fn daylight(&self) -> f32 {
+        self.config.day.daylight(self.tick as f32)
+    }
+
+    pub fn is_night(&self) -> bool {
+        self.daylight() < 0.5
+    }
+
+    // Recharge à la station, réduite par l'obscurité
+    pub fn recharge_rate(&self) -> f32 {
+        let day = &self.config.day;
+        let recharge = self.config.robots.battery_recharge;
+        if !day.is_enabled() {
+            return recharge;
+        }
+        recharge * (day.night_recharge + (1.0 - day.night_recharge) * self.daylight())
+    }
+
+    // L'explorateur doit rentrer s'il n'aurait plus le temps de regagner la station de jour
+    pub(crate) fn nightfall_near(&self, explorer: &RobotExplorer) -> bool {
+        if !self.config.day.is_enabled() {
+            return false;
+        }
+        let distance = explorer
+            .x
+            .abs_diff(explorer.station_x)
+            .max(explorer.y.abs_diff(explorer.station_y));
+        self.config.day.ticks_until_night(self.tick) <= (distance + 1) * RETURN_TICKS_PER_CELL
+    }
+}
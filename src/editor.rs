@@ -0,0 +1,215 @@
+use crate::map::Map;
+use crate::station::ResourceKind;
+use crate::ELEVATION_SCALE;
+
+// Pinceaux du mode bac à sable
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Brush {
+    Obstacle,
+    Energy,
+    Minerals,
+    Clear,
+    Station,
+}
+
+impl Brush {
+    pub const ALL: [Brush; 5] = [
+        Brush::Obstacle,
+        Brush::Energy,
+        Brush::Minerals,
+        Brush::Clear,
+        Brush::Station,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Brush::Obstacle => "obstacle",
+            Brush::Energy => "energy",
+            Brush::Minerals => "minerals",
+            Brush::Clear => "clear",
+            Brush::Station => "station",
+        }
+    }
+}
+
+impl Map {
+    // Description d'une case pour l'infobulle d'inspection
+    pub fn describe_cell(&self, x: usize, y: usize) -> Vec<String> {
+        let resource = if self.energy[y][x] {
+            "energy"
+        } else if self.minerals[y][x] {
+            "minerals"
+        } else {
+            "none"
+        };
+        let mut lines = vec![
+            format!("Cell ({}, {})", x, y),
+            format!(
+                "{:?}, elevation {:.0} m, moisture {:.2}",
+                self.biomes[y][x],
+                self.elevation[y][x] * ELEVATION_SCALE,
+                self.moisture[y][x]
+            ),
+            format!("Resource: {}", resource),
+            format!(
+                "Obstacle: {}, {:?}, explored: {}",
+                self.obstacles[y][x],
+                self.visibility(x, y),
+                self.explored[y][x]
+            ),
+        ];
+        if (x, y) == (self.station.x, self.station.y) {
+            lines.push("Station".to_string());
+        }
+        if self.station.pending_sites.contains(&(x, y)) {
+            lines.push("Site pending at the station".to_string());
+        }
+        for (index, explorer) in self.robot_explorers.iter().enumerate() {
+            if (explorer.x, explorer.y) == (x, y) {
+                lines.push(format!(
+                    "Explorer {}: {}",
+                    index,
+                    explorer.status(&self.config.robots)
+                ));
+            }
+        }
+        for (index, extractor) in self.robot_extractors.iter().enumerate() {
+            if (extractor.x, extractor.y) == (x, y) {
+                lines.push(format!(
+                    "Extractor {}: {}",
+                    index,
+                    extractor.status(&self.config.robots)
+                ));
+            }
+        }
+        lines
+    }
+
+    fn has_robot(&self, x: usize, y: usize) -> bool {
+        self.robot_explorers
+            .iter()
+            .map(|e| (e.x, e.y))
+            .chain(self.robot_extractors.iter().map(|e| (e.x, e.y)))
+            .any(|pos| pos == (x, y))
+    }
+
+    // Retire une ressource ainsi que toutes les références des robots et de la station
+    fn remove_resource(&mut self, x: usize, y: usize) {
+        self.energy[y][x] = false;
+        self.minerals[y][x] = false;
+        self.resources.retain(|&pos| pos != (x, y));
+        self.station.pending_sites.retain(|&pos| pos != (x, y));
+        self.station.known_sites.remove(&(x, y));
+        for explorer in self.robot_explorers.iter_mut() {
+            if explorer.resource_position == Some((x, y)) {
+                explorer.resource_position = None;
+                explorer.founded_resource = false;
+            }
+            explorer.reported_sites.remove(&(x, y));
+        }
+        for extractor in self.robot_extractors.iter_mut() {
+            if extractor.target_position == Some((x, y)) {
+                extractor.target_position = None;
+            }
+        }
+    }
+
+    // Applique un pinceau sur une case ; renvoie false si la modification est refusée.
+    // Les robots recalculent leur chemin à chaque tick, la modification est donc prise
+    // en compte dès le tick suivant.
+    pub fn paint(&mut self, x: usize, y: usize, brush: Brush) -> bool {
+        if x >= self.size || y >= self.size {
+            return false;
+        }
+        let station = (self.station.x, self.station.y);
+        let is_station = (x, y) == station;
+
+        match brush {
+            Brush::Obstacle => {
+                if is_station || self.has_robot(x, y) || self.obstacles[y][x] {
+                    return false;
+                }
+                self.remove_resource(x, y);
+                self.obstacles[y][x] = true;
+            }
+            Brush::Energy | Brush::Minerals => {
+                let energy = brush == Brush::Energy;
+                if is_station || (self.energy[y][x] == energy && self.minerals[y][x] != energy) {
+                    return false;
+                }
+                self.remove_resource(x, y);
+                self.obstacles[y][x] = false;
+                // Comme un gisement apparu en cours de mission, la case est à réexplorer
+                let kind = if energy {
+                    ResourceKind::Energy
+                } else {
+                    ResourceKind::Minerals
+                };
+                self.add_deposit((x, y), kind);
+            }
+            Brush::Clear => {
+                if !self.obstacles[y][x] && !self.energy[y][x] && !self.minerals[y][x] {
+                    return false;
+                }
+                self.remove_resource(x, y);
+                self.obstacles[y][x] = false;
+            }
+            Brush::Station => {
+                if is_station {
+                    return false;
+                }
+                self.remove_resource(x, y);
+                self.obstacles[y][x] = false;
+                self.fog_of_war[y][x] = false;
+                self.station.x = x;
+                self.station.y = y;
+                for explorer in self.robot_explorers.iter_mut() {
+                    explorer.station_x = x;
+                    explorer.station_y = y;
+                }
+                for extractor in self.robot_extractors.iter_mut() {
+                    extractor.station_x = x;
+                    extractor.station_y = y;
+                }
+            }
+        }
+
+        // L'ancienne case de la station change aussi d'apparence si elle est déplacée
+        self.damage.mark(x, y);
+        self.damage.mark(station.0, station.1);
+        self.refresh_reachability();
+        self.update_visibility();
+        // Une carte modifiée relance une mission déjà terminée
+        self.game_over = false;
+        self.outcome = None;
+        self.last_progress_tick = self.tick;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::tests::{flat_map, place};
+    use crate::mission::{ExplorerStrategy, MissionConfig, MissionOutcome};
+
+    #[test]
+    fn deposits_painted_on_explored_cells_are_collected() {
+        let mut map = flat_map(MissionConfig {
+            map_size: 6,
+            strategy: ExplorerStrategy::Frontier,
+            ..MissionConfig::default()
+        });
+        place(&mut map, (4, 1), ResourceKind::Energy);
+        map.run_headless();
+        assert_eq!(map.collected, 1);
+        assert!(map.explored[3][3]);
+
+        assert!(map.paint(3, 3, Brush::Minerals));
+        assert!(!map.explored[3][3]);
+        let summary = map.run_headless();
+        assert_eq!(summary.outcome, Some(MissionOutcome::AllResourcesCollected));
+        assert_eq!((summary.collected, summary.remaining), (2, 0));
+        assert_eq!(map.station.minerals, 1);
+    }
+}
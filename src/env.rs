@@ -0,0 +1,527 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+
+use crate::agent::{parse_robot_id, robot_id, Order};
+use crate::brain::Action;
+use crate::camera::RobotRef;
+use crate::map::{DispatchMode, Map};
+use crate::mission::{MissionConfig, MissionOutcome};
+use crate::terrain::GenerationSettings;
+
+pub const DEFAULT_PATCH_RADIUS: usize = 3;
+
+// Couche d'information du voisinage observé par un robot
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Channel {
+    // 1 pour une case inconnue ou hors de la carte
+    Fog,
+    // 1 pour un obstacle connu ou hors de la carte
+    Obstacle,
+    Resource,
+    Explored,
+    // Altitude connue entre 0 et 1, 0 si inconnue
+    Elevation,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 5] = [
+        Channel::Fog,
+        Channel::Obstacle,
+        Channel::Resource,
+        Channel::Explored,
+        Channel::Elevation,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Channel::Fog => "fog",
+            Channel::Obstacle => "obstacle",
+            Channel::Resource => "resource",
+            Channel::Explored => "explored",
+            Channel::Elevation => "elevation",
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Channel, String> {
+        Channel::ALL
+            .into_iter()
+            .find(|channel| channel.label() == text)
+            .ok_or_else(|| {
+                format!(
+                    "unknown observation channel '{}', expected fog, obstacle, resource, explored or elevation",
+                    text
+                )
+            })
+    }
+}
+
+// Encodage des observations : fenêtre carrée centrée sur chaque robot
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObservationEncoding {
+    pub radius: usize,
+    pub channels: Vec<Channel>,
+}
+
+impl Default for ObservationEncoding {
+    fn default() -> Self {
+        ObservationEncoding {
+            radius: DEFAULT_PATCH_RADIUS,
+            channels: vec![Channel::Fog, Channel::Obstacle, Channel::Resource],
+        }
+    }
+}
+
+impl ObservationEncoding {
+    pub fn side(&self) -> usize {
+        2 * self.radius + 1
+    }
+
+    // Valeurs de la fenêtre autour de (x, y), canal par canal puis ligne par ligne
+    fn patch(&self, map: &Map, (x, y): (usize, usize)) -> Vec<f32> {
+        let side = self.side();
+        let mut values = Vec::with_capacity(self.channels.len() * side * side);
+        for &channel in &self.channels {
+            for dy in 0..side {
+                for dx in 0..side {
+                    let cx = (x + dx)
+                        .checked_sub(self.radius)
+                        .filter(|&cx| cx < map.size);
+                    let cy = (y + dy)
+                        .checked_sub(self.radius)
+                        .filter(|&cy| cy < map.size);
+                    values.push(match (cx, cy) {
+                        (Some(cx), Some(cy)) => cell_value(map, channel, cx, cy),
+                        _ => outside_value(channel),
+                    });
+                }
+            }
+        }
+        values
+    }
+}
+
+fn cell_value(map: &Map, channel: Channel, x: usize, y: usize) -> f32 {
+    let known = !map.fog_of_war[y][x];
+    let flag = |value: bool| if value { 1.0 } else { 0.0 };
+    match channel {
+        Channel::Fog => flag(!known),
+        Channel::Obstacle => flag(known && map.obstacles[y][x]),
+        Channel::Resource => flag(known && (map.seen_energy[y][x] || map.seen_minerals[y][x])),
+        Channel::Explored => flag(map.explored[y][x]),
+        Channel::Elevation if known => map.elevation[y][x] as f32,
+        Channel::Elevation => 0.0,
+    }
+}
+
+fn outside_value(channel: Channel) -> f32 {
+    match channel {
+        Channel::Fog | Channel::Obstacle => 1.0,
+        _ => 0.0,
+    }
+}
+
+// Poids des termes de la récompense
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RewardShaping {
+    // Par case découverte
+    pub discovery: f32,
+    // Par ressource déposée à la station
+    pub delivery: f32,
+    // Retranché à chaque tick
+    pub step_penalty: f32,
+}
+
+impl Default for RewardShaping {
+    fn default() -> Self {
+        RewardShaping {
+            discovery: 0.1,
+            delivery: 1.0,
+            step_penalty: 0.01,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RobotObservation {
+    pub id: String,
+    pub x: usize,
+    pub y: usize,
+    // Charge entre 0 et 1
+    pub battery: f32,
+    pub carrying: bool,
+    // Position de la station relativement au robot
+    pub station: [i64; 2],
+    pub patch: Vec<f32>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Observation {
+    pub tick: usize,
+    // Dimensions de chaque fenêtre : canaux, lignes, colonnes
+    pub patch_shape: [usize; 3],
+    pub robots: Vec<RobotObservation>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Info {
+    pub discovered: usize,
+    pub delivered: usize,
+    pub outcome: Option<String>,
+    pub success: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Step {
+    pub observation: Observation,
+    pub reward: f32,
+    pub done: bool,
+    pub info: Info,
+}
+
+// Environnement d'apprentissage par renforcement au-dessus de la simulation sans affichage
+pub struct Env {
+    pub config: MissionConfig,
+    pub settings: GenerationSettings,
+    pub dispatch_mode: DispatchMode,
+    pub encoding: ObservationEncoding,
+    pub rewards: RewardShaping,
+    map: Map,
+}
+
+fn known_cells(map: &Map) -> usize {
+    map.fog_of_war.iter().flatten().filter(|&&fog| !fog).count()
+}
+
+impl Env {
+    pub fn new(
+        config: MissionConfig,
+        settings: GenerationSettings,
+        encoding: ObservationEncoding,
+        rewards: RewardShaping,
+    ) -> Self {
+        let map = Map::with_seed(0, &config);
+        Env {
+            config,
+            settings,
+            dispatch_mode: DispatchMode::Concurrent,
+            encoding,
+            rewards,
+            map,
+        }
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        let (mut map, _) = Map::generate(seed, &self.settings, &self.config);
+        map.dispatch_mode = self.dispatch_mode;
+        map.verbose = false;
+        self.map = map;
+        self.observe()
+    }
+
+    // Joue un tick ; les robots sans action gardent leur comportement intégré
+    pub fn step(&mut self, actions: Vec<(RobotRef, Action)>) -> Step {
+        if self.map.game_over {
+            return Step {
+                observation: self.observe(),
+                reward: 0.0,
+                done: true,
+                info: Info::default(),
+            };
+        }
+
+        let known = known_cells(&self.map);
+        let collected = self.map.collected;
+        self.map.orders = actions;
+        self.map.update_robot();
+
+        let map = &self.map;
+        let discovered = known_cells(map).saturating_sub(known);
+        let delivered = map.collected - collected;
+        let rewards = &self.rewards;
+        let reward = discovered as f32 * rewards.discovery + delivered as f32 * rewards.delivery
+            - rewards.step_penalty;
+        Step {
+            observation: self.observe(),
+            reward,
+            done: map.game_over,
+            info: Info {
+                discovered,
+                delivered,
+                outcome: map.outcome.map(|outcome| format!("{:?}", outcome)),
+                success: map.outcome.is_some_and(MissionOutcome::is_success),
+            },
+        }
+    }
+
+    pub fn observe(&self) -> Observation {
+        let map = &self.map;
+        let capacity = map.config.robots.battery_capacity;
+        let station = (map.station.x as i64, map.station.y as i64);
+        let robot = |robot: RobotRef, (x, y): (usize, usize), battery: f32, carrying: bool| {
+            RobotObservation {
+                id: robot_id(robot),
+                x,
+                y,
+                battery: battery / capacity,
+                carrying,
+                station: [station.0 - x as i64, station.1 - y as i64],
+                patch: self.encoding.patch(map, (x, y)),
+            }
+        };
+        let explorers = map
+            .robot_explorers
+            .iter()
+            .enumerate()
+            .map(|(index, e)| robot(RobotRef::Explorer(index), (e.x, e.y), e.battery, false));
+        let extractors = map.robot_extractors.iter().enumerate().map(|(index, e)| {
+            robot(
+                RobotRef::Extractor(index),
+                (e.x, e.y),
+                e.battery,
+                e.cargo.is_some(),
+            )
+        });
+        let side = self.encoding.side();
+        Observation {
+            tick: map.tick,
+            patch_shape: [self.encoding.channels.len(), side, side],
+            robots: explorers.chain(extractors).collect(),
+        }
+    }
+}
+
+// Requêtes du programme d'apprentissage, une ligne JSON chacune
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case", deny_unknown_fields)]
+enum Request {
+    Reset {
+        seed: Option<u64>,
+    },
+    Step {
+        #[serde(default)]
+        actions: BTreeMap<String, Order>,
+    },
+    Close,
+}
+
+#[derive(Serialize)]
+struct ErrorReply {
+    error: String,
+}
+
+#[derive(Serialize)]
+struct ResetReply {
+    observation: Observation,
+    channels: Vec<Channel>,
+}
+
+impl Env {
+    // Sert l'environnement sur un flux ; sans graine, l'épisode n utilise `seed + n`
+    pub fn serve(
+        &mut self,
+        seed: u64,
+        reader: impl Read,
+        mut writer: impl Write,
+    ) -> Result<usize, String> {
+        let mut episodes = 0;
+        for line in BufReader::new(reader).lines() {
+            let line = line.map_err(|error| error.to_string())?;
+            let reply = match serde_json::from_str::<Request>(&line) {
+                Ok(Request::Reset { seed: episode_seed }) => {
+                    let episode_seed =
+                        episode_seed.unwrap_or_else(|| seed.wrapping_add(episodes as u64));
+                    episodes += 1;
+                    serde_json::to_string(&ResetReply {
+                        observation: self.reset(episode_seed),
+                        channels: self.encoding.channels.clone(),
+                    })
+                }
+                Ok(Request::Step { .. }) if episodes == 0 => serde_json::to_string(&ErrorReply {
+                    error: "reset the environment before stepping it".to_string(),
+                }),
+                Ok(Request::Step { actions }) => {
+                    let mut orders = vec![];
+                    let mut unknown = vec![];
+                    for (id, order) in &actions {
+                        match parse_robot_id(id, &self.map) {
+                            Some(robot) => orders.extend(order.action().map(|a| (robot, a))),
+                            None => unknown.push(id.as_str()),
+                        }
+                    }
+                    if unknown.is_empty() {
+                        serde_json::to_string(&self.step(orders))
+                    } else {
+                        serde_json::to_string(&ErrorReply {
+                            error: format!("unknown robots: {}", unknown.join(", ")),
+                        })
+                    }
+                }
+                Ok(Request::Close) => break,
+                Err(error) => serde_json::to_string(&ErrorReply {
+                    error: error.to_string(),
+                }),
+            }
+            .expect("replies are always serializable");
+            writeln!(writer, "{}", reply)
+                .and_then(|_| writer.flush())
+                .map_err(|error| error.to_string())?;
+        }
+        Ok(episodes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::tests::{flat_map, place};
+    use crate::mission::ExplorerStrategy;
+    use crate::station::ResourceKind;
+
+    fn config() -> MissionConfig {
+        MissionConfig {
+            map_size: 6,
+            explorers: 1,
+            extractors: 1,
+            strategy: ExplorerStrategy::Frontier,
+            ..MissionConfig::default()
+        }
+    }
+
+    fn env(encoding: ObservationEncoding) -> Env {
+        Env::new(
+            config(),
+            GenerationSettings::default(),
+            encoding,
+            RewardShaping::default(),
+        )
+    }
+
+    fn json(value: &impl Serialize) -> String {
+        serde_json::to_string(value).unwrap()
+    }
+
+    #[test]
+    fn reset_is_deterministic_for_a_seed() {
+        let mut env = env(ObservationEncoding::default());
+        let first = json(&env.reset(9));
+        let steps: Vec<String> = (0..20).map(|_| json(&env.step(vec![]))).collect();
+
+        assert_eq!(json(&env.reset(9)), first);
+        for step in &steps {
+            assert_eq!(&json(&env.step(vec![])), step);
+        }
+        assert_ne!(json(&env.reset(10)), first);
+    }
+
+    #[test]
+    fn patches_are_padded_outside_the_map() {
+        let encoding = ObservationEncoding {
+            radius: 2,
+            channels: Channel::ALL.to_vec(),
+        };
+        let mut env = env(encoding);
+        env.map = flat_map(config());
+        let observation = env.observe();
+        assert_eq!(observation.patch_shape, [5, 5, 5]);
+
+        // Station en (0, 0) : les deux premières lignes et colonnes sont hors de la carte
+        let patch = &observation.robots[0].patch;
+        assert_eq!(patch.len(), 5 * 5 * 5);
+        let value = |channel: usize, dx: usize, dy: usize| patch[channel * 25 + dy * 5 + dx];
+        for (channel, outside) in [(0, 1.0), (1, 1.0), (2, 0.0), (3, 0.0), (4, 0.0)] {
+            assert_eq!(value(channel, 0, 3), outside);
+            assert_eq!(value(channel, 4, 1), outside);
+        }
+        // Voisinage du robot connu, à mi-altitude ; au-delà des capteurs, encore inconnu
+        assert_eq!(value(0, 2, 2), 0.0);
+        assert_eq!(value(4, 2, 2), 0.5);
+        assert_eq!(value(0, 3, 3), 0.0);
+        assert_eq!(value(0, 4, 4), 1.0);
+        assert_eq!(value(4, 4, 4), 0.0);
+    }
+
+    #[test]
+    fn rewards_follow_discoveries_and_deliveries() {
+        let mut env = env(ObservationEncoding::default());
+        env.map = flat_map(config());
+        place(&mut env.map, (3, 2), ResourceKind::Energy);
+
+        let rewards = RewardShaping::default();
+        let (mut reported, mut delivered) = (false, false);
+        loop {
+            let known_sites = env.map.station.known_sites.len();
+            let step = env.step(vec![]);
+            let info = &step.info;
+            let expected = info.discovered as f32 * rewards.discovery
+                + info.delivered as f32 * rewards.delivery
+                - rewards.step_penalty;
+            assert!((step.reward - expected).abs() < 1e-6);
+            if env.map.station.known_sites.len() > known_sites {
+                // Signaler un site ne rapporte rien en soi
+                assert_eq!(info.delivered, 0);
+                reported = true;
+            }
+            if info.delivered == 1 {
+                assert!(step.reward >= rewards.delivery - rewards.step_penalty);
+                delivered = true;
+            }
+            if step.done {
+                assert!(info.success);
+                assert_eq!(info.outcome.as_deref(), Some("AllResourcesCollected"));
+                break;
+            }
+        }
+        assert!(reported && delivered);
+
+        // Un épisode terminé reste terminé jusqu'à la remise à zéro
+        let tick = env.map.tick;
+        let step = env.step(vec![]);
+        assert!(step.done);
+        assert_eq!((step.reward, step.observation.tick), (0.0, tick));
+        env.reset(3);
+        assert!(!env.step(vec![]).done);
+        assert_eq!(env.map.tick, 1);
+    }
+
+    #[test]
+    fn serve_answers_each_request() {
+        let requests = [
+            r#"{"cmd": "step"}"#,
+            r#"{"cmd": "reset", "seed": 4}"#,
+            r#"{"cmd": "step", "actions": {"explorer-7": {"action": "wait"}}}"#,
+            r#"{"cmd": "step", "actions": {"explorer-0": {"action": "wait"}}}"#,
+            r#"{"cmd": "jump"}"#,
+            r#"{"cmd": "close"}"#,
+            r#"{"cmd": "reset"}"#,
+        ]
+        .join("\n");
+        let mut env = env(ObservationEncoding::default());
+        let mut output = vec![];
+        assert_eq!(env.serve(4, requests.as_bytes(), &mut output), Ok(1));
+
+        let replies: Vec<serde_json::Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(replies.len(), 5);
+        assert_eq!(
+            replies[0]["error"],
+            "reset the environment before stepping it"
+        );
+        assert_eq!(replies[1]["observation"]["tick"], 0);
+        assert_eq!(
+            replies[1]["channels"],
+            serde_json::json!(["fog", "obstacle", "resource"])
+        );
+        assert_eq!(replies[2]["error"], "unknown robots: explorer-7");
+        assert_eq!(replies[3]["observation"]["tick"], 1);
+        assert_eq!(replies[3]["done"], false);
+        assert!(replies[4]["error"]
+            .as_str()
+            .unwrap()
+            .contains("unknown variant `jump`"));
+    }
+}
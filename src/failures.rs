@@ -0,0 +1,276 @@
+use rand::Rng;
+use std::mem;
+
+use crate::camera::RobotRef;
+use crate::map::Map;
+use crate::neighbors;
+use crate::robot::find_path;
+use crate::station::ResourceKind;
+use crate::{RobotExtractor, MAX_HEALTH, REPAIR_RATE};
+
+impl Map {
+    // Usure du tick : réparation à la station, pannes aléatoires ailleurs
+    pub(crate) fn update_failures(&mut self) {
+        let mut explorers = mem::take(&mut self.robot_explorers);
+        for (index, explorer) in explorers.iter_mut().enumerate() {
+            if self.wear(
+                explorer.is_at_station(),
+                explorer.broken,
+                &mut explorer.health,
+            ) {
+                explorer.broken = true;
+                self.break_down(RobotRef::Explorer(index), (explorer.x, explorer.y));
+            }
+        }
+        self.robot_explorers = explorers;
+
+        let mut extractors = mem::take(&mut self.robot_extractors);
+        for (index, extractor) in extractors.iter_mut().enumerate() {
+            if self.wear(
+                extractor.is_at_station(),
+                extractor.broken,
+                &mut extractor.health,
+            ) {
+                extractor.broken = true;
+                self.break_down(RobotRef::Extractor(index), (extractor.x, extractor.y));
+                self.abandon_tasks(extractor);
+            }
+        }
+        self.robot_extractors = extractors;
+    }
+
+    // Renvoie true si le robot vient de tomber en panne ; le générateur n'est
+    // sollicité que si les pannes aléatoires sont activées
+    fn wear(&mut self, at_station: bool, broken: bool, health: &mut f32) -> bool {
+        if broken {
+            return false;
+        }
+        if at_station {
+            *health = (*health + REPAIR_RATE).min(MAX_HEALTH);
+            return false;
+        }
+        let rate = self.config.robots.breakdown_rate;
+        if rate > 0.0 && self.rng.gen_bool(rate) {
+            *health = 0.0;
+        }
+        *health <= 0.0
+    }
+
+    fn break_down(&mut self, robot: RobotRef, (x, y): (usize, usize)) {
+        self.log(format_args!("{} broke down at ({}, {}).", robot, x, y));
+        self.station.rescues.push_back((robot, (x, y)));
+        self.metrics.breakdowns += 1;
+    }
+
+    // Un extracteur en panne lâche sa cargaison et rend ses missions à la station
+    fn abandon_tasks(&mut self, extractor: &mut RobotExtractor) {
+        if let Some(kind) = extractor.cargo.take() {
+            self.drop_cargo((extractor.x, extractor.y), kind);
+        }
+        if let Some(site) = extractor.target_position.take() {
+            self.station.pending_sites.push_back(site);
+        }
+        if let Some(rescue) = extractor.rescue.take() {
+            self.station.rescues.push_front(rescue);
+        }
+    }
+
+    // La cargaison devient une ressource sur la case du robot ou une case voisine libre,
+    // signalée à la station par radio
+    fn drop_cargo(&mut self, (x, y): (usize, usize), kind: ResourceKind) {
+        let cell = std::iter::once((x, y))
+            .chain(neighbors(x, y, self.size))
+            .find(|&(cx, cy)| {
+                !self.obstacles[cy][cx]
+                    && !self.energy[cy][cx]
+                    && !self.minerals[cy][cx]
+                    && (cx, cy) != (self.station.x, self.station.y)
+            });
+        let (cx, cy) = match cell {
+            Some(cell) => cell,
+            None => {
+                self.log(format_args!("{:?} lost at ({}, {}).", kind, x, y));
+                return;
+            }
+        };
+        match kind {
+            ResourceKind::Energy => self.energy[cy][cx] = true,
+            ResourceKind::Minerals => self.minerals[cy][cx] = true,
+        }
+        self.damage.mark(cx, cy);
+        // Le site a pu être signalé puis récolté auparavant
+        self.station.known_sites.remove(&(cx, cy));
+        self.station.report_site((cx, cy));
+        self.log(format_args!("{:?} dropped at ({}, {}).", kind, cx, cy));
+    }
+
+    // Envoie un extracteur libre, rechargé, à la station vers le premier robot en panne accessible
+    // hors des zones dangereuses
+    pub(crate) fn assign_rescue(&mut self, index: usize, extractor: &mut RobotExtractor) {
+        if extractor.is_busy()
+            || extractor.broken
+            || !extractor.is_at_station()
+            || extractor.battery < self.config.robots.battery_capacity
+        {
+            return;
+        }
+        let station = (self.station.x, self.station.y);
+        let rescue = self
+            .station
+            .rescues
+            .iter()
+            .position(|&(robot, position)| {
+                robot != RobotRef::Extractor(index)
+                    && !self.in_hazard(position.0, position.1)
+                    && find_path(self, station, position, false).is_some()
+            })
+            .and_then(|position| self.station.rescues.remove(position));
+
+        if let Some((robot, (x, y))) = rescue {
+            extractor.rescue = Some((robot, (x, y)));
+            self.log(format_args!(
+                "Station sent Extractor {} to rescue {} at ({}, {}).",
+                index, robot, x, y
+            ));
+        }
+    }
+
+    // Trajet du dépanneur jusqu'au robot en panne ; la réparation a lieu en fin de tick
+    pub(crate) fn rescue_trip(&mut self, extractor: &mut RobotExtractor) {
+        let (robot, (x, y)) = match extractor.rescue {
+            Some(rescue) => rescue,
+            None => return,
+        };
+        self.recharge_extractor(extractor);
+        match extractor.move_towards(x, y, self) {
+            Some(path) => {
+                if let Some((next_x, next_y)) = self.step_along(
+                    &path,
+                    false,
+                    &mut extractor.move_progress,
+                    &mut extractor.battery,
+                ) {
+                    extractor.x = next_x;
+                    extractor.y = next_y;
+                }
+            }
+            None => {
+                // Robot devenu inaccessible : un autre dépanneur tentera sa chance
+                self.station.rescues.push_back((robot, (x, y)));
+                extractor.rescue = None;
+            }
+        }
+    }
+
+    // Les dépanneurs arrivés à destination réparent le robot en panne ; dans une zone
+    // dangereuse, la réparation serait aussitôt défaite et le dépannage est remis à plus tard
+    pub(crate) fn repair_robots(&mut self) {
+        for index in 0..self.robot_extractors.len() {
+            let rescuer = &self.robot_extractors[index];
+            let (robot, position) = match rescuer.rescue {
+                Some((robot, position))
+                    if !rescuer.broken && (rescuer.x, rescuer.y) == position =>
+                {
+                    (robot, position)
+                }
+                _ => continue,
+            };
+            if self.in_hazard(position.0, position.1) {
+                self.robot_extractors[index].rescue = None;
+                self.station.rescues.push_back((robot, position));
+                self.log(format_args!(
+                    "Extractor {} left {} until the hazard at ({}, {}) clears.",
+                    index, robot, position.0, position.1
+                ));
+                continue;
+            }
+            let (health, broken) = match robot {
+                RobotRef::Explorer(i) => {
+                    let e = &mut self.robot_explorers[i];
+                    (&mut e.health, &mut e.broken)
+                }
+                RobotRef::Extractor(i) => {
+                    let e = &mut self.robot_extractors[i];
+                    (&mut e.health, &mut e.broken)
+                }
+            };
+            *health = (*health + REPAIR_RATE).min(MAX_HEALTH);
+            if *health < MAX_HEALTH && *broken {
+                continue;
+            }
+            *broken = false;
+            self.robot_extractors[index].rescue = None;
+            self.metrics.repairs += 1;
+            self.log(format_args!("Extractor {} repaired {}.", index, robot));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hazards::{ActiveEvent, Event, EventKind};
+    use crate::mission::MissionConfig;
+    use crate::terrain::GenerationSettings;
+
+    // Explorateur 0 en panne sur la case de la station, dépanneur déjà sur place
+    fn rescue_in_progress() -> (Map, (usize, usize)) {
+        let (mut map, _) =
+            Map::generate(2, &GenerationSettings::default(), &MissionConfig::default());
+        map.verbose = false;
+        let position = (map.station.x, map.station.y);
+        let explorer = &mut map.robot_explorers[0];
+        (explorer.x, explorer.y) = position;
+        explorer.health = 0.0;
+        explorer.broken = true;
+        let rescuer = &mut map.robot_extractors[0];
+        (rescuer.x, rescuer.y) = position;
+        rescuer.rescue = Some((RobotRef::Explorer(0), position));
+        (map, position)
+    }
+
+    #[test]
+    fn repairs_a_robot_once_the_rescuer_arrives() {
+        let (mut map, _) = rescue_in_progress();
+        map.repair_robots();
+        assert_eq!(map.robot_explorers[0].health, REPAIR_RATE);
+        assert!(map.robot_extractors[0].rescue.is_some());
+    }
+
+    #[test]
+    fn postpones_repairs_inside_a_hazard() {
+        let (mut map, position) = rescue_in_progress();
+        map.events.push(ActiveEvent {
+            event: Event {
+                kind: EventKind::Hazard {
+                    duration: 10,
+                    damage: 1.0,
+                },
+                x: position.0,
+                y: position.1,
+                radius: 1,
+            },
+            until: map.tick + 9,
+        });
+        map.repair_robots();
+
+        assert_eq!(map.robot_explorers[0].health, 0.0);
+        assert!(map.robot_explorers[0].broken);
+        assert_eq!(map.robot_extractors[0].rescue, None);
+        assert_eq!(
+            map.station.rescues.back(),
+            Some(&(RobotRef::Explorer(0), position))
+        );
+
+        // Aucun dépanneur n'y retourne tant que la zone est active
+        let mut rescuer = map.robot_extractors[1].clone();
+        rescuer.battery = map.config.robots.battery_capacity;
+        (rescuer.x, rescuer.y) = (map.station.x, map.station.y);
+        map.assign_rescue(1, &mut rescuer);
+        assert_eq!(rescuer.rescue, None);
+
+        map.events.clear();
+        map.assign_rescue(1, &mut rescuer);
+        assert_eq!(rescuer.rescue, Some((RobotRef::Explorer(0), position)));
+    }
+}
@@ -0,0 +1,257 @@
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{self, Color, Rect};
+use ggez::{event, timer, Context, GameResult};
+
+use crate::camera::{self, Camera, RobotRef};
+use crate::clock::SimClock;
+use crate::editor::Brush;
+use crate::hud;
+use crate::map::Map;
+use crate::overlay;
+use crate::render::{self, MapLayer, Tileset};
+use crate::{CELL_SIZE, MAX_WINDOW_SIZE};
+
+const ZOOM_STEP: f32 = 1.2;
+// Au-delà de ce déplacement, un appui gauche est un glissé et non un clic
+const CLICK_TOLERANCE: f32 = 4.0;
+const MARKER_COLOR: Color = Color::new(1.0, 0.0, 1.0, 1.0);
+
+// État de la fenêtre : la simulation et les ressources graphiques
+pub struct Game {
+    pub map: Map,
+    pub clock: SimClock,
+    pub tileset: Tileset,
+    pub map_layer: MapLayer,
+    pub camera: Camera,
+    pub show_minimap: bool,
+    pub show_hud: bool,
+    pub show_frame_time: bool,
+    pub show_path_overlay: bool,
+    // Case inspectée par un clic
+    pub inspected: Option<(usize, usize)>,
+    // Pinceau courant, le mode bac à sable est actif s'il est défini
+    pub brush: Option<Brush>,
+    pub painting: bool,
+    pub pressed_at: Option<(f32, f32)>,
+}
+
+impl Game {
+    pub fn new(ctx: &mut Context, map: Map) -> Self {
+        let size = map.size;
+        Game {
+            clock: SimClock::new(&map),
+            map,
+            tileset: Tileset::load(ctx),
+            map_layer: MapLayer::new(size),
+            camera: Camera::new(size),
+            // La minicarte n'est utile que si la planète dépasse la fenêtre
+            show_minimap: size as f32 * CELL_SIZE > MAX_WINDOW_SIZE,
+            show_hud: true,
+            show_frame_time: false,
+            show_path_overlay: false,
+            inspected: None,
+            brush: None,
+            painting: false,
+            pressed_at: None,
+        }
+    }
+
+    fn paint_at(&mut self, ctx: &mut Context, position: (f32, f32)) {
+        let screen = graphics::screen_coordinates(ctx);
+        if let (Some(brush), Some((x, y))) = (self.brush, self.camera.cell_at(screen, position)) {
+            if self.map.paint(x, y, brush) {
+                if let Some(recorder) = &mut self.clock.recorder {
+                    recorder.record_edit(x, y, brush);
+                }
+            }
+        }
+    }
+}
+
+impl event::EventHandler for Game {
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        graphics::clear(ctx, graphics::WHITE);
+
+        let screen = graphics::screen_coordinates(ctx);
+        let positions = self.clock.interpolated_positions(&self.map);
+        self.camera.update(&self.map, &positions);
+        self.camera.push(ctx)?;
+        self.map_layer.invalidate(&self.map.damage.take());
+        self.map_layer.draw(
+            ctx,
+            &self.map,
+            &self.tileset,
+            self.camera.visible_area(screen),
+        )?;
+        // Le temps restant avant le prochain tick adoucit la tombée de la nuit
+        let time = (self.map.tick as f32 - 1.0 + self.clock.alpha(&self.map)).max(0.0);
+        render::draw_night(ctx, &self.map, time)?;
+        render::draw_events(ctx, &self.map)?;
+        if self.show_path_overlay {
+            overlay::draw_path_overlay(ctx, &self.map)?;
+        }
+        render::draw_robots(ctx, &self.map, &self.tileset, &positions)?;
+        if let Some(cell) = self.inspected {
+            render::draw_cell_marker(ctx, cell, MARKER_COLOR)?;
+        }
+        self.camera.pop(ctx)?;
+
+        if self.show_hud {
+            hud::draw_hud(ctx, &self.map, &self.clock, self.brush)?;
+        }
+        if let Some((x, y)) = self.inspected {
+            let corner = ((x + 1) as f32 * CELL_SIZE, (y + 1) as f32 * CELL_SIZE);
+            let anchor = self.camera.world_to_screen(screen, corner);
+            hud::draw_tooltip(ctx, &self.map.describe_cell(x, y), anchor)?;
+        }
+        if self.show_path_overlay {
+            overlay::draw_overlay_legend(ctx, &self.map)?;
+        }
+        if self.show_minimap {
+            camera::draw_minimap(ctx, &self.map, &self.camera)?;
+        }
+        if self.show_frame_time {
+            render::draw_frame_time(ctx, self.map_layer.rebuilt_last_frame)?;
+        }
+
+        graphics::present(ctx)?;
+        Ok(())
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        self.clock.advance(&mut self.map, timer::delta(ctx));
+        Ok(())
+    }
+
+    fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        match button {
+            MouseButton::Left if self.brush.is_some() => {
+                self.painting = true;
+                self.paint_at(ctx, (x, y));
+            }
+            MouseButton::Left => {
+                self.camera.dragging = true;
+                self.pressed_at = Some((x, y));
+            }
+            // Le bouton droit déplace la vue, y compris en mode bac à sable
+            MouseButton::Right => self.camera.dragging = true,
+            _ => {}
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        match button {
+            MouseButton::Left => {
+                self.painting = false;
+                self.camera.dragging = false;
+                if let Some((px, py)) = self.pressed_at.take() {
+                    if (x - px).abs() <= CLICK_TOLERANCE && (y - py).abs() <= CLICK_TOLERANCE {
+                        let screen = graphics::screen_coordinates(ctx);
+                        let cell = self.camera.cell_at(screen, (x, y));
+                        // Un second clic sur la même case ferme l'infobulle
+                        self.inspected = if cell == self.inspected { None } else { cell };
+                    }
+                }
+            }
+            MouseButton::Right => self.camera.dragging = false,
+            _ => {}
+        }
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, dx: f32, dy: f32) {
+        if self.camera.dragging {
+            self.camera.pan(dx, dy);
+        }
+        // Le pinceau station ne s'applique qu'au clic
+        if self.painting && self.brush != Some(Brush::Station) {
+            self.paint_at(ctx, (x, y));
+        }
+    }
+
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, _x: f32, y: f32) {
+        let screen = graphics::screen_coordinates(ctx);
+        let cursor = ggez::input::mouse::position(ctx);
+        let factor = if y > 0.0 { ZOOM_STEP } else { 1.0 / ZOOM_STEP };
+        self.camera.zoom_at(factor, screen, (cursor.x, cursor.y));
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
+        let _ = graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, width, height));
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, mods: KeyMods, repeat: bool) {
+        // Pas de déplacement dans une relecture : 1 tick, ou 100 avec Maj
+        let seek_step = if mods.contains(KeyMods::SHIFT) {
+            100
+        } else {
+            1
+        };
+        let screen = graphics::screen_coordinates(ctx);
+        let center = (screen.w / 2.0, screen.h / 2.0);
+        match keycode {
+            KeyCode::Left => self.camera.pan(CELL_SIZE, 0.0),
+            KeyCode::Right => self.camera.pan(-CELL_SIZE, 0.0),
+            KeyCode::Up => self.camera.pan(0.0, CELL_SIZE),
+            KeyCode::Down => self.camera.pan(0.0, -CELL_SIZE),
+            KeyCode::Equals | KeyCode::Add => self.camera.zoom_at(ZOOM_STEP, screen, center),
+            KeyCode::Minus | KeyCode::Subtract => {
+                self.camera.zoom_at(1.0 / ZOOM_STEP, screen, center)
+            }
+            // Les déplacements dans une relecture se répètent tant que la touche est enfoncée
+            KeyCode::N | KeyCode::Period if self.clock.playback.is_some() => {
+                let tick = self.map.tick + seek_step;
+                self.clock.seek(&mut self.map, tick);
+            }
+            KeyCode::Comma => {
+                let tick = self.map.tick.saturating_sub(seek_step);
+                self.clock.seek(&mut self.map, tick);
+            }
+            _ if repeat => {}
+            // Tab : sélectionne le robot suivant et le suit
+            KeyCode::Tab => {
+                self.camera.selected = RobotRef::next(self.camera.selected, &self.map);
+                self.camera.follow = self.camera.selected.is_some();
+            }
+            KeyCode::F => {
+                self.camera.follow = !self.camera.follow && self.camera.selected.is_some();
+            }
+            KeyCode::Home => {
+                self.camera.follow = false;
+                self.camera
+                    .focus_cell(self.map.station.x, self.map.station.y);
+            }
+            KeyCode::Space => self.clock.paused = !self.clock.paused,
+            // Pas à pas : N ou point, de préférence en pause
+            KeyCode::N | KeyCode::Period => self.clock.step(&mut self.map),
+            KeyCode::Back => self.clock.seek(&mut self.map, 0),
+            KeyCode::RBracket | KeyCode::PageUp => self.clock.faster(),
+            KeyCode::LBracket | KeyCode::PageDown => self.clock.slower(),
+            // E : entre dans le mode bac à sable ou le quitte
+            KeyCode::E if self.clock.playback.is_none() => {
+                self.brush = match self.brush {
+                    Some(_) => None,
+                    None => Some(Brush::Obstacle),
+                };
+                self.painting = false;
+            }
+            KeyCode::Key1 | KeyCode::Key2 | KeyCode::Key3 | KeyCode::Key4 | KeyCode::Key5
+                if self.brush.is_some() =>
+            {
+                let index = keycode as usize - KeyCode::Key1 as usize;
+                self.brush = Some(Brush::ALL[index]);
+            }
+            // P : calque des chemins prévus et des recherches A*
+            KeyCode::P => {
+                self.show_path_overlay = !self.show_path_overlay;
+                self.map.trace_searches = self.show_path_overlay;
+            }
+            // V : vue divine, sans brouillard
+            KeyCode::V => self.map_layer.god_view = !self.map_layer.god_view,
+            KeyCode::H => self.show_hud = !self.show_hud,
+            KeyCode::M => self.show_minimap = !self.show_minimap,
+            KeyCode::F3 => self.show_frame_time = !self.show_frame_time,
+            KeyCode::Escape => event::quit(ctx),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use rand::Rng;
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::map::{grid, Grid, Map};
+use crate::metrics::Metrics;
+use crate::mission::{MissionConfig, StationPlacement};
+use crate::neighbors;
+use crate::station::Station;
+use crate::terrain::{Biome, GenerationSettings};
+use crate::{RobotExplorer, RobotExtractor};
+
+const MAX_REROLLS: u64 = 10;
+
+// Rapport de génération de la carte
+#[derive(Clone, Debug)]
+pub struct GenerationReport {
+    pub seed: u64,
+    pub rerolls: u64,
+    pub regions: usize,
+    pub largest_region: usize,
+    pub reachable_percent: f32,
+    pub carved_cells: usize,
+    pub resources: usize,
+}
+
+impl fmt::Display for GenerationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Map generated (seed {}, {} rerolls): {} resources, {} regions, largest {} cells, {:.1}% reachable, {} cells carved",
+            self.seed,
+            self.rerolls,
+            self.resources,
+            self.regions,
+            self.largest_region,
+            self.reachable_percent,
+            self.carved_cells
+        )
+    }
+}
+
+impl Map {
+    // Génère une carte dont toutes les ressources sont accessibles depuis la station
+    pub fn generate(
+        seed: u64,
+        settings: &GenerationSettings,
+        config: &MissionConfig,
+    ) -> (Map, GenerationReport) {
+        let mut rerolls = 0;
+        let mut map = loop {
+            let mut map = Map::with_seed(seed.wrapping_add(rerolls), config);
+            map.place_terrain(settings);
+            map.place_resources(settings);
+            map.place_obstacles(settings);
+
+            if map.place_station() {
+                break map;
+            }
+            if rerolls >= MAX_REROLLS {
+                // Dernier recours : libère le centre de la carte et y installe la station,
+                // connect_resources creusera les accès
+                let center = map.size / 2;
+                map.clear_cell(center, center);
+                map.settle_station(center, center);
+                break map;
+            }
+            rerolls += 1;
+        };
+
+        let carved_cells = map.connect_resources();
+        map.refresh_reachability();
+        map.update_visibility();
+        let report = map.generation_report(seed, rerolls, carved_cells);
+        (map, report)
+    }
+
+    // Place la station sur une case libre de la plus grande région praticable
+    pub fn place_station(&mut self) -> bool {
+        let (x, y) = match self.config.station {
+            StationPlacement::Fixed(x, y) => {
+                // La case imposée est dégagée, connect_resources creusera les accès
+                self.clear_cell(x, y);
+                (x, y)
+            }
+            placement => {
+                let (labels, sizes) = self.regions();
+                let largest = match (0..sizes.len()).max_by_key(|&id| sizes[id]) {
+                    Some(id) => id,
+                    None => return false,
+                };
+
+                let candidates: Vec<(usize, usize)> = (0..self.size)
+                    .flat_map(|y| (0..self.size).map(move |x| (x, y)))
+                    .filter(|&(x, y)| {
+                        labels[y][x] == Some(largest) && !self.energy[y][x] && !self.minerals[y][x]
+                    })
+                    .collect();
+                if candidates.is_empty() {
+                    return false;
+                }
+
+                if placement == StationPlacement::Center {
+                    let center = self.size / 2;
+                    *candidates
+                        .iter()
+                        .min_by_key(|&&(x, y)| {
+                            x.abs_diff(center).pow(2) + y.abs_diff(center).pow(2)
+                        })
+                        .unwrap()
+                } else {
+                    candidates[self.rng.gen_range(0..candidates.len())]
+                }
+            }
+        };
+        self.settle_station(x, y);
+        true
+    }
+
+    fn clear_cell(&mut self, x: usize, y: usize) {
+        self.obstacles[y][x] = false;
+        self.energy[y][x] = false;
+        self.minerals[y][x] = false;
+        self.resources.retain(|&pos| pos != (x, y));
+    }
+
+    // Installe la station et la flotte au complet sur la case donnée
+    fn settle_station(&mut self, x: usize, y: usize) {
+        let battery = self.config.robots.battery_capacity;
+        self.station = Station::new(x, y);
+        self.robot_explorers = (0..self.config.explorers)
+            .map(|_| RobotExplorer::new(x, y, battery))
+            .collect();
+        self.robot_extractors = (0..self.config.extractors)
+            .map(|_| RobotExtractor::new(x, y, battery))
+            .collect();
+        self.metrics = Metrics::new(self.config.explorers, self.config.extractors);
+        self.fog_of_war[y][x] = false;
+    }
+
+    // Étiquette les régions connexes de cases praticables et renvoie leurs tailles
+    pub fn regions(&self) -> (Grid<Option<usize>>, Vec<usize>) {
+        let mut labels = grid(self.size, None);
+        let mut sizes = vec![];
+
+        for start_y in 0..self.size {
+            for start_x in 0..self.size {
+                if self.obstacles[start_y][start_x] || labels[start_y][start_x].is_some() {
+                    continue;
+                }
+
+                let id = sizes.len();
+                let mut size = 0;
+                let mut stack = vec![(start_x, start_y)];
+                labels[start_y][start_x] = Some(id);
+                while let Some((x, y)) = stack.pop() {
+                    size += 1;
+                    for (nx, ny) in neighbors(x, y, self.size) {
+                        if !self.obstacles[ny][nx] && labels[ny][nx].is_none() {
+                            labels[ny][nx] = Some(id);
+                            stack.push((nx, ny));
+                        }
+                    }
+                }
+                sizes.push(size);
+            }
+        }
+
+        (labels, sizes)
+    }
+
+    // Creuse des couloirs vers chaque ressource isolée et renvoie le nombre d'obstacles retirés
+    pub fn connect_resources(&mut self) -> usize {
+        let mut carved = 0;
+        let resources = self.resources.clone();
+
+        for (x, y) in resources {
+            if self.reachable_cells()[y][x] {
+                continue;
+            }
+            for (cx, cy) in self.cheapest_corridor((x, y)) {
+                if self.obstacles[cy][cx] {
+                    self.obstacles[cy][cx] = false;
+                    carved += 1;
+                }
+            }
+        }
+
+        carved
+    }
+
+    // Parcours 0-1 BFS : traverser un obstacle coûte 1, une case libre 0
+    fn cheapest_corridor(&self, goal: (usize, usize)) -> Vec<(usize, usize)> {
+        let start = (self.station.x, self.station.y);
+        let mut cost = grid(self.size, usize::MAX);
+        let mut came_from = grid(self.size, None);
+        let mut queue = VecDeque::new();
+        cost[start.1][start.0] = 0;
+        queue.push_back(start);
+
+        while let Some((x, y)) = queue.pop_front() {
+            if (x, y) == goal {
+                break;
+            }
+            for (nx, ny) in neighbors(x, y, self.size) {
+                let step = self.obstacles[ny][nx] as usize;
+                if cost[y][x] + step < cost[ny][nx] {
+                    cost[ny][nx] = cost[y][x] + step;
+                    came_from[ny][nx] = Some((x, y));
+                    if step == 0 {
+                        queue.push_front((nx, ny));
+                    } else {
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+        }
+
+        let mut path = vec![];
+        let mut current = Some(goal);
+        while let Some((x, y)) = current {
+            path.push((x, y));
+            current = came_from[y][x];
+        }
+        path
+    }
+
+    pub fn generation_report(
+        &self,
+        seed: u64,
+        rerolls: u64,
+        carved_cells: usize,
+    ) -> GenerationReport {
+        let (_, sizes) = self.regions();
+        let passable: usize = sizes.iter().sum();
+        let reachable = self
+            .reachable_cells()
+            .iter()
+            .flatten()
+            .filter(|&&val| val)
+            .count();
+
+        GenerationReport {
+            seed,
+            rerolls,
+            regions: sizes.len(),
+            largest_region: sizes.iter().copied().max().unwrap_or(0),
+            reachable_percent: if passable == 0 {
+                0.0
+            } else {
+                reachable as f32 * 100.0 / passable as f32
+            },
+            carved_cells,
+            resources: self.resources.len(),
+        }
+    }
+
+    // Aperçu textuel de la carte générée, pour ajuster les paramètres
+    pub fn preview(&self) -> String {
+        let mut preview = String::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let symbol = if (x, y) == (self.station.x, self.station.y) {
+                    'S'
+                } else if self.obstacles[y][x] {
+                    '#'
+                } else if self.energy[y][x] {
+                    'E'
+                } else if self.minerals[y][x] {
+                    'M'
+                } else {
+                    self.biomes[y][x].symbol()
+                };
+                preview.push(symbol);
+            }
+            preview.push('\n');
+        }
+
+        for biome in Biome::ALL {
+            let count = self
+                .biomes
+                .iter()
+                .flatten()
+                .filter(|&&b| b == biome)
+                .count();
+            preview.push_str(&format!(
+                "{} {:?}: {:.1}%\n",
+                biome.symbol(),
+                biome,
+                count as f32 * 100.0 / (self.size * self.size) as f32
+            ));
+        }
+        preview.push_str("S station, # obstacle, E energy, M minerals\n");
+        preview
+    }
+}
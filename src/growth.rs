@@ -0,0 +1,260 @@
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+use rand::Rng;
+
+use crate::map::Map;
+use crate::station::ResourceKind;
+
+// Cases tirées au hasard parmi lesquelles le bruit choisit l'emplacement d'un gisement
+const SPAWN_CANDIDATES: usize = 16;
+// Dérive du bruit d'apparition à chaque tick, pour que les zones fertiles se déplacent
+const SPAWN_DRIFT: f64 = 0.002;
+
+// Renouvellement des ressources pour les missions de longue durée, désactivé par défaut
+#[derive(Clone, Debug, PartialEq)]
+pub struct Growth {
+    // Ticks avant qu'un gisement récolté ne repousse, 0 pour jamais
+    pub regrow_ticks: usize,
+    // Probabilité par tick qu'un nouveau gisement apparaisse
+    pub spawn_rate: f64,
+    // Fréquence spatiale du bruit qui guide les apparitions
+    pub spawn_frequency: f64,
+}
+
+impl Default for Growth {
+    fn default() -> Self {
+        Growth {
+            regrow_ticks: 0,
+            spawn_rate: 0.0,
+            spawn_frequency: 0.15,
+        }
+    }
+}
+
+impl Growth {
+    pub fn is_enabled(&self) -> bool {
+        self.regrow_ticks > 0 || self.spawn_rate > 0.0
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if !(0.0..=1.0).contains(&self.spawn_rate) {
+            errors.push(format!(
+                "spawn_rate must be between 0 and 1, got {}",
+                self.spawn_rate
+            ));
+        }
+        if self.spawn_frequency <= 0.0 {
+            errors.push("spawn_frequency must be positive".to_string());
+        }
+        errors
+    }
+}
+
+impl Map {
+    // Mémorise un gisement récolté pour le faire repousser plus tard
+    pub(crate) fn deplete(&mut self, position: (usize, usize), kind: ResourceKind) {
+        if self.config.growth.regrow_ticks > 0 {
+            self.depleted.push_back((self.tick, position, kind));
+        }
+    }
+
+    // Repousse des gisements épuisés puis apparition éventuelle d'un nouveau gisement
+    pub(crate) fn update_growth(&mut self) {
+        let regrow_ticks = self.config.growth.regrow_ticks;
+        while let Some(&(harvested, (x, y), kind)) = self.depleted.front() {
+            if harvested + regrow_ticks > self.tick {
+                break;
+            }
+            self.depleted.pop_front();
+            // Une case recouverte entre-temps ne repousse pas
+            if self.is_free_for_deposit(x, y) {
+                self.add_deposit((x, y), kind);
+                self.log(format_args!("{:?} regrew at ({}, {}).", kind, x, y));
+            }
+        }
+
+        // Le générateur n'est sollicité que si les apparitions sont activées
+        let rate = self.config.growth.spawn_rate;
+        if rate > 0.0 && self.rng.gen_bool(rate) {
+            if let Some((position, kind)) = self.spawn_site() {
+                self.add_deposit(position, kind);
+                self.log(format_args!(
+                    "New {:?} deposit at ({}, {}).",
+                    kind, position.0, position.1
+                ));
+            }
+        }
+    }
+
+    fn is_free_for_deposit(&self, x: usize, y: usize) -> bool {
+        !self.obstacles[y][x]
+            && !self.energy[y][x]
+            && !self.minerals[y][x]
+            && (x, y) != (self.station.x, self.station.y)
+    }
+
+    // Parmi quelques cases libres, retient celle où le bruit d'apparition est le plus fort ;
+    // le biome oriente la nature du gisement comme lors de la génération
+    fn spawn_site(&mut self) -> Option<((usize, usize), ResourceKind)> {
+        let seed = match self.spawn_seed {
+            Some(seed) => seed,
+            None => {
+                let seed = self.rng.gen();
+                self.spawn_seed = Some(seed);
+                seed
+            }
+        };
+        let noise = Fbm::<Perlin>::new(seed)
+            .set_octaves(2)
+            .set_frequency(self.config.growth.spawn_frequency);
+        let time = self.tick as f64 * SPAWN_DRIFT;
+
+        let candidates: Vec<(usize, usize)> = (0..SPAWN_CANDIDATES)
+            .map(|_| {
+                (
+                    self.rng.gen_range(0..self.size),
+                    self.rng.gen_range(0..self.size),
+                )
+            })
+            .collect();
+        let (x, y) = candidates
+            .into_iter()
+            .filter(|&(x, y)| self.is_free_for_deposit(x, y))
+            .max_by(|&(ax, ay), &(bx, by)| {
+                let a = noise.get([ax as f64, ay as f64, time]);
+                let b = noise.get([bx as f64, by as f64, time]);
+                a.total_cmp(&b)
+            })?;
+
+        let biome = self.biomes[y][x];
+        let energy_chance = (0.5 + biome.energy_bias() - biome.minerals_bias()).clamp(0.1, 0.9);
+        let kind = if self.rng.gen_bool(energy_chance) {
+            ResourceKind::Energy
+        } else {
+            ResourceKind::Minerals
+        };
+        Some(((x, y), kind))
+    }
+
+    // La case redevient à explorer : les explorateurs doivent y repasser pour signaler le gisement
+    pub(crate) fn add_deposit(&mut self, (x, y): (usize, usize), kind: ResourceKind) {
+        match kind {
+            ResourceKind::Energy => self.energy[y][x] = true,
+            ResourceKind::Minerals => self.minerals[y][x] = true,
+        }
+        self.damage.mark(x, y);
+        self.explored[y][x] = false;
+        if !self.resources.contains(&(x, y)) {
+            self.resources.push((x, y));
+        }
+        self.station.known_sites.remove(&(x, y));
+        for explorer in self.robot_explorers.iter_mut() {
+            explorer.reported_sites.remove(&(x, y));
+        }
+    }
+
+    // Ressources ramenées à la station pour 1000 ticks de mission
+    pub fn throughput(&self) -> f32 {
+        self.collected as f32 * 1000.0 / self.tick.max(1) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::Brush;
+    use crate::map::tests::{flat_map, place};
+    use crate::mission::{ExplorerStrategy, MissionConfig, MissionOutcome, Victory};
+
+    fn config(growth: Growth) -> MissionConfig {
+        MissionConfig {
+            map_size: 8,
+            strategy: ExplorerStrategy::Frontier,
+            growth,
+            ..MissionConfig::default()
+        }
+    }
+
+    fn regrowing(regrow_ticks: usize) -> Map {
+        let mut map = flat_map(config(Growth {
+            regrow_ticks,
+            ..Growth::default()
+        }));
+        // Gisement récolté au tick 10 sur une case déjà explorée
+        map.tick = 10;
+        map.explored[3][4] = true;
+        map.deplete((4, 3), ResourceKind::Minerals);
+        map
+    }
+
+    #[test]
+    fn harvested_sites_regrow_after_the_delay() {
+        let mut map = regrowing(30);
+        map.tick = 39;
+        map.update_growth();
+        assert!(!map.minerals[3][4]);
+
+        map.tick = 40;
+        map.update_growth();
+        assert!(map.minerals[3][4]);
+        assert!(!map.explored[3][4]);
+        assert!(map.resources.contains(&(4, 3)));
+        assert!(map.depleted.is_empty());
+    }
+
+    #[test]
+    fn covered_sites_do_not_regrow() {
+        let mut map = regrowing(30);
+        assert!(map.paint(4, 3, Brush::Obstacle));
+        map.tick = 40;
+        map.update_growth();
+        assert!(!map.minerals[3][4]);
+        assert!(map.depleted.is_empty());
+    }
+
+    #[test]
+    fn spawned_deposits_land_on_free_unexplored_cells() {
+        let spawn = || {
+            let mut map = flat_map(config(Growth {
+                spawn_rate: 1.0,
+                ..Growth::default()
+            }));
+            map.obstacles[5][5] = true;
+            for row in map.explored.iter_mut() {
+                row.fill(true);
+            }
+            for _ in 0..10 {
+                map.tick += 1;
+                map.update_growth();
+            }
+            map
+        };
+        let map = spawn();
+        assert!(!map.resources.is_empty());
+        for &(x, y) in &map.resources {
+            assert!(map.energy[y][x] || map.minerals[y][x]);
+            assert!(!map.explored[y][x]);
+            assert!(!map.obstacles[y][x] && (x, y) != (0, 0));
+        }
+        assert_eq!(spawn().resources, map.resources);
+    }
+
+    #[test]
+    fn endless_colonies_harvest_regrowing_sites() {
+        let mut map = flat_map(MissionConfig {
+            max_ticks: 600,
+            victory: Victory::Endless,
+            ..config(Growth {
+                regrow_ticks: 20,
+                ..Growth::default()
+            })
+        });
+        place(&mut map, (3, 3), ResourceKind::Energy);
+
+        let summary = map.run_headless();
+        assert_eq!(summary.outcome, Some(MissionOutcome::ColonySustained));
+        assert_eq!(summary.ticks, 600);
+        assert!(summary.collected > 1);
+        assert_eq!(summary.throughput, map.collected as f32 * 1000.0 / 600.0);
+    }
+}
@@ -0,0 +1,328 @@
+use rand::Rng;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::map::Map;
+
+pub const DEFAULT_HAZARD_DAMAGE: f32 = 2.0;
+// Rayon et durée des événements tirés au hasard
+const RANDOM_RADIUS: RangeInclusive<usize> = 1..=3;
+const RANDOM_DURATION: RangeInclusive<usize> = 20..=60;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EventKind {
+    // Aveugle les capteurs des explorateurs pris dans la zone
+    DustStorm { duration: usize },
+    // Recouvre de rochers les cases libres de la zone
+    Rockfall,
+    // Endommage les robots présents dans la zone à chaque tick
+    Hazard { duration: usize, damage: f32 },
+}
+
+// Événement couvrant le carré de rayon `radius` centré sur (x, y)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Event {
+    pub kind: EventKind,
+    pub x: usize,
+    pub y: usize,
+    pub radius: usize,
+}
+
+impl Event {
+    pub fn label(&self) -> &'static str {
+        match self.kind {
+            EventKind::DustStorm { .. } => "dust_storm",
+            EventKind::Rockfall => "rockfall",
+            EventKind::Hazard { .. } => "hazard",
+        }
+    }
+
+    pub fn covers(&self, x: usize, y: usize) -> bool {
+        x.abs_diff(self.x) <= self.radius && y.abs_diff(self.y) <= self.radius
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            EventKind::DustStorm { .. } => "Dust storm",
+            EventKind::Rockfall => "Rockfall",
+            EventKind::Hazard { .. } => "Hazardous zone",
+        }
+    }
+
+    // Inverse de l'affichage : `dust_storm 3,4 2 30`, `rockfall 3,4 1`, `hazard 3,4 2 30 1.5`
+    pub fn parse(words: &[&str]) -> Option<Event> {
+        let (label, cell, radius, rest) = match words {
+            [label, cell, radius, rest @ ..] => (*label, *cell, *radius, rest),
+            _ => return None,
+        };
+        let (x, y) = cell.split_once(',')?;
+        let kind = match (label, rest) {
+            ("dust_storm", [duration]) => EventKind::DustStorm {
+                duration: duration.parse().ok()?,
+            },
+            ("rockfall", []) => EventKind::Rockfall,
+            ("hazard", [duration, damage]) => EventKind::Hazard {
+                duration: duration.parse().ok()?,
+                damage: damage.parse().ok()?,
+            },
+            _ => return None,
+        };
+        Some(Event {
+            kind,
+            x: x.parse().ok()?,
+            y: y.parse().ok()?,
+            radius: radius.parse().ok()?,
+        })
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {},{} {}", self.label(), self.x, self.y, self.radius)?;
+        match self.kind {
+            EventKind::DustStorm { duration } => write!(f, " {}", duration),
+            EventKind::Rockfall => Ok(()),
+            EventKind::Hazard { duration, damage } => write!(f, " {} {}", duration, damage),
+        }
+    }
+}
+
+// Événement prévu par le scénario, déclenché au début du tick indiqué
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScheduledEvent {
+    pub tick: usize,
+    pub event: Event,
+}
+
+// Événements environnementaux de la mission, aucun par défaut
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hazards {
+    // Probabilités par tick d'un événement aléatoire de chaque type
+    pub storm_rate: f64,
+    pub rockfall_rate: f64,
+    pub hazard_rate: f64,
+    // Perte de santé par tick dans les zones dangereuses tirées au hasard
+    pub hazard_damage: f32,
+    pub scheduled: Vec<ScheduledEvent>,
+}
+
+impl Default for Hazards {
+    fn default() -> Self {
+        Hazards {
+            storm_rate: 0.0,
+            rockfall_rate: 0.0,
+            hazard_rate: 0.0,
+            hazard_damage: DEFAULT_HAZARD_DAMAGE,
+            scheduled: vec![],
+        }
+    }
+}
+
+impl Hazards {
+    pub fn validate(&self, map_size: usize) -> Vec<String> {
+        let mut errors = vec![];
+        for (name, rate) in [
+            ("storm_rate", self.storm_rate),
+            ("rockfall_rate", self.rockfall_rate),
+            ("hazard_rate", self.hazard_rate),
+        ] {
+            if !(0.0..=1.0).contains(&rate) {
+                errors.push(format!("{} must be between 0 and 1, got {}", name, rate));
+            }
+        }
+        if self.hazard_damage < 0.0 {
+            errors.push("hazard_damage must not be negative".to_string());
+        }
+        for scheduled in &self.scheduled {
+            let event = &scheduled.event;
+            if event.x >= map_size || event.y >= map_size {
+                errors.push(format!(
+                    "{} at tick {}: position ({}, {}) is outside the {}x{} map",
+                    event.label(),
+                    scheduled.tick,
+                    event.x,
+                    event.y,
+                    map_size,
+                    map_size
+                ));
+            }
+            if scheduled.tick == 0 {
+                errors.push(format!("{}: tick must be positive", event.label()));
+            }
+            match event.kind {
+                EventKind::DustStorm { duration: 0 } | EventKind::Hazard { duration: 0, .. } => {
+                    errors.push(format!(
+                        "{} at tick {}: duration must be positive",
+                        event.label(),
+                        scheduled.tick
+                    ))
+                }
+                EventKind::Hazard { damage, .. } if damage < 0.0 => errors.push(format!(
+                    "hazard at tick {}: damage must not be negative",
+                    scheduled.tick
+                )),
+                _ => {}
+            }
+        }
+        errors
+    }
+}
+
+// Tempête ou zone dangereuse en cours, jusqu'au tick `until` inclus
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ActiveEvent {
+    pub event: Event,
+    pub until: usize,
+}
+
+impl Map {
+    // Déclenche les événements du tick, puis applique les dégâts des zones dangereuses
+    pub(crate) fn update_hazards(&mut self) {
+        let tick = self.tick;
+        self.events.retain(|active| active.until >= tick);
+
+        let mut events: Vec<Event> = self
+            .config
+            .hazards
+            .scheduled
+            .iter()
+            .filter(|scheduled| scheduled.tick == tick)
+            .map(|scheduled| scheduled.event)
+            .collect();
+        events.extend(self.random_events());
+        for event in events {
+            self.start_event(event);
+        }
+
+        for active in &self.events {
+            if let EventKind::Hazard { damage, .. } = active.event.kind {
+                let robots = self
+                    .robot_explorers
+                    .iter_mut()
+                    .map(|e| (e.x, e.y, &mut e.health))
+                    .chain(
+                        self.robot_extractors
+                            .iter_mut()
+                            .map(|e| (e.x, e.y, &mut e.health)),
+                    );
+                for (x, y, health) in robots {
+                    if active.event.covers(x, y) {
+                        *health = (*health - damage).max(0.0);
+                    }
+                }
+            }
+        }
+    }
+
+    // Le générateur n'est sollicité que pour les taux non nuls
+    fn random_events(&mut self) -> Vec<Event> {
+        let hazards = &self.config.hazards;
+        let rates = [
+            hazards.storm_rate,
+            hazards.rockfall_rate,
+            hazards.hazard_rate,
+        ];
+        let damage = hazards.hazard_damage;
+        let mut events = vec![];
+        for (index, rate) in rates.into_iter().enumerate() {
+            if rate <= 0.0 || !self.rng.gen_bool(rate) {
+                continue;
+            }
+            let duration = self.rng.gen_range(RANDOM_DURATION);
+            let kind = match index {
+                0 => EventKind::DustStorm { duration },
+                1 => EventKind::Rockfall,
+                _ => EventKind::Hazard { duration, damage },
+            };
+            events.push(Event {
+                kind,
+                x: self.rng.gen_range(0..self.size),
+                y: self.rng.gen_range(0..self.size),
+                radius: self.rng.gen_range(RANDOM_RADIUS),
+            });
+        }
+        events
+    }
+
+    fn start_event(&mut self, event: Event) {
+        match event.kind {
+            EventKind::Rockfall => {
+                let blocked = self.rockfall(&event);
+                self.log(format_args!(
+                    "{} at ({}, {}) blocked {} cells.",
+                    event.name(),
+                    event.x,
+                    event.y,
+                    blocked
+                ));
+            }
+            EventKind::DustStorm { duration } | EventKind::Hazard { duration, .. } => {
+                self.log(format_args!(
+                    "{} at ({}, {}), radius {}, for {} ticks.",
+                    event.name(),
+                    event.x,
+                    event.y,
+                    event.radius,
+                    duration
+                ));
+                self.events.push(ActiveEvent {
+                    event,
+                    until: self.tick + duration - 1,
+                });
+            }
+        }
+    }
+
+    // Les rochers épargnent la station, les robots, les ressources et les sites signalés
+    fn rockfall(&mut self, event: &Event) -> usize {
+        let robots: Vec<(usize, usize)> = self
+            .robot_explorers
+            .iter()
+            .map(|e| (e.x, e.y))
+            .chain(self.robot_extractors.iter().map(|e| (e.x, e.y)))
+            .collect();
+        let mut blocked = 0;
+        for y in event.y.saturating_sub(event.radius)..=(event.y + event.radius).min(self.size - 1)
+        {
+            for x in
+                event.x.saturating_sub(event.radius)..=(event.x + event.radius).min(self.size - 1)
+            {
+                let spared = self.obstacles[y][x]
+                    || (x, y) == (self.station.x, self.station.y)
+                    || robots.contains(&(x, y))
+                    || self.energy[y][x]
+                    || self.minerals[y][x]
+                    || self.station.pending_sites.contains(&(x, y));
+                if !spared {
+                    self.obstacles[y][x] = true;
+                    self.damage.mark(x, y);
+                    blocked += 1;
+                }
+            }
+        }
+        blocked
+    }
+
+    // Case couverte par une zone dangereuse en cours
+    pub fn in_hazard(&self, x: usize, y: usize) -> bool {
+        self.events.iter().any(|active| {
+            matches!(active.event.kind, EventKind::Hazard { .. }) && active.event.covers(x, y)
+        })
+    }
+
+    // Les capteurs pris dans une tempête ne voient plus que leur propre case,
+    // et la nuit réduit la portée de tous les capteurs
+    pub fn sensor_range_at(&self, x: usize, y: usize) -> usize {
+        let in_storm = self.events.iter().any(|active| {
+            matches!(active.event.kind, EventKind::DustStorm { .. }) && active.event.covers(x, y)
+        });
+        let range = self.config.robots.sensor_range;
+        if in_storm {
+            0
+        } else if self.is_night() {
+            range.min(self.config.day.night_sensor_range)
+        } else {
+            range
+        }
+    }
+}
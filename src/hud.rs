@@ -0,0 +1,195 @@
+use ggez::graphics::{self, Color, DrawParam, MeshBuilder, Rect, Text};
+use ggez::{Context, GameResult};
+
+use crate::clock::SimClock;
+use crate::editor::Brush;
+use crate::map::Map;
+use crate::mission::Victory;
+use crate::{
+    DEFAULT_COLOR, ENERGY_COLOR, FOG_COLOR, MINERALS_COLOR, OBSTACLE_COLOR, ROBOT_EXPLORER_COLOR,
+    ROBOT_EXTRACTOR_COLOR, SCIENCE_INTERESTS_COLOR, STATION_COLOR,
+};
+
+const MARGIN: f32 = 8.0;
+const PADDING: f32 = 6.0;
+const SWATCH_SIZE: f32 = 10.0;
+const PANEL_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.6);
+const TEXT_COLOR: Color = Color::new(1.0, 1.0, 1.0, 1.0);
+
+const LEGEND: [(&str, Color); 9] = [
+    ("explorer", ROBOT_EXPLORER_COLOR),
+    ("extractor", ROBOT_EXTRACTOR_COLOR),
+    ("station", STATION_COLOR),
+    ("energy", ENERGY_COLOR),
+    ("minerals", MINERALS_COLOR),
+    ("science", SCIENCE_INTERESTS_COLOR),
+    ("obstacle", OBSTACLE_COLOR),
+    ("ground", DEFAULT_COLOR),
+    ("fog", FOG_COLOR),
+];
+
+// Texte du panneau : statistiques de mission puis état de chaque robot
+fn mission_lines(map: &Map, clock: &SimClock, brush: Option<Brush>) -> Vec<String> {
+    let summary = map.summary();
+    let mut lines = vec![
+        format!("Tick {} at {}", map.tick, clock.label()),
+        format!("Consumables left: {}", map.count_consumables()),
+        format!("Explored: {:.1}%", summary.explored_percent),
+        format!(
+            "Station: {} energy, {} minerals, {} sites pending",
+            map.station.energy,
+            map.station.minerals,
+            map.station.pending_sites.len()
+        ),
+    ];
+    let day = &map.config.day;
+    if day.is_enabled() {
+        lines.push(format!(
+            "Day {}, {} ({:.0}% daylight)",
+            map.tick / day.length + 1,
+            if map.is_night() { "night" } else { "daytime" },
+            map.daylight() * 100.0
+        ));
+    }
+    if map.config.growth.is_enabled() || map.config.victory == Victory::Endless {
+        lines.push(format!(
+            "Throughput: {:.1} resources per 1000 ticks",
+            summary.throughput
+        ));
+    }
+    for active in &map.events {
+        lines.push(format!(
+            "{} at ({}, {}) until tick {}",
+            active.event.name(),
+            active.event.x,
+            active.event.y,
+            active.until
+        ));
+    }
+    if let Some(outcome) = map.outcome {
+        lines.push(format!("Mission over: {:?}", outcome));
+    }
+    if let Some(tick) = clock.playback.as_ref().and_then(|p| p.diverged_at) {
+        lines.push(format!("Replay diverged at tick {}", tick));
+    }
+    if let Some(brush) = brush {
+        lines.push(format!(
+            "Edit mode: {} brush (1-5 to switch)",
+            brush.label()
+        ));
+    }
+
+    for (index, explorer) in map.robot_explorers.iter().enumerate() {
+        lines.push(format!(
+            "Explorer {} ({}, {}) {:>3.0}% {}",
+            index,
+            explorer.x,
+            explorer.y,
+            explorer.battery,
+            explorer.status(&map.config.robots)
+        ));
+    }
+    for (index, extractor) in map.robot_extractors.iter().enumerate() {
+        lines.push(format!(
+            "Extractor {} ({}, {}) {:>3.0}% {}",
+            index,
+            extractor.x,
+            extractor.y,
+            extractor.battery,
+            extractor.status(&map.config.robots)
+        ));
+    }
+    lines
+}
+
+// Panneau translucide en haut à gauche : statistiques, robots et légende des couleurs
+pub fn draw_hud(
+    ctx: &mut Context,
+    map: &Map,
+    clock: &SimClock,
+    brush: Option<Brush>,
+) -> GameResult {
+    let text = Text::new(mission_lines(map, clock, brush).join("\n"));
+    let (text_w, text_h) = text.dimensions(ctx);
+    let legend = LEGEND.map(|(label, color)| (Text::new(label), color));
+    let line_h = legend[0].0.height(ctx) as f32;
+    let legend_w = legend
+        .iter()
+        .map(|(label, _)| label.width(ctx) as f32)
+        .fold(0.0, f32::max)
+        + SWATCH_SIZE
+        + PADDING;
+    let legend_h = LEGEND.len() as f32 * (SWATCH_SIZE + 4.0);
+
+    let origin = (MARGIN, MARGIN);
+    let panel = Rect::new(
+        origin.0,
+        origin.1,
+        (text_w as f32).max(legend_w) + 2.0 * PADDING,
+        text_h as f32 + legend_h + 3.0 * PADDING,
+    );
+    let legend_y = origin.1 + 2.0 * PADDING + text_h as f32;
+
+    let mut mesh = MeshBuilder::new();
+    mesh.rectangle(graphics::DrawMode::fill(), panel, PANEL_COLOR);
+    for (row, (_, color)) in LEGEND.iter().enumerate() {
+        let swatch = Rect::new(
+            origin.0 + PADDING,
+            legend_y + row as f32 * (SWATCH_SIZE + 4.0),
+            SWATCH_SIZE,
+            SWATCH_SIZE,
+        );
+        mesh.rectangle(graphics::DrawMode::fill(), swatch, *color);
+        mesh.rectangle(graphics::DrawMode::stroke(1.0), swatch, TEXT_COLOR);
+    }
+    let mesh = mesh.build(ctx)?;
+    graphics::draw(ctx, &mesh, DrawParam::default())?;
+
+    graphics::draw(
+        ctx,
+        &text,
+        DrawParam::default()
+            .dest([origin.0 + PADDING, origin.1 + PADDING])
+            .color(TEXT_COLOR),
+    )?;
+    for (row, (label, _)) in legend.iter().enumerate() {
+        graphics::draw(
+            ctx,
+            label,
+            DrawParam::default()
+                .dest([
+                    origin.0 + 2.0 * PADDING + SWATCH_SIZE,
+                    legend_y + row as f32 * (SWATCH_SIZE + 4.0) + (SWATCH_SIZE - line_h) / 2.0,
+                ])
+                .color(TEXT_COLOR),
+        )?;
+    }
+    Ok(())
+}
+
+// Infobulle d'inspection d'une case, placée à un point de l'écran
+pub fn draw_tooltip(ctx: &mut Context, lines: &[String], position: (f32, f32)) -> GameResult {
+    let text = Text::new(lines.join("\n"));
+    let (text_w, text_h) = text.dimensions(ctx);
+    let screen = graphics::screen_coordinates(ctx);
+    let width = text_w as f32 + 2.0 * PADDING;
+    let height = text_h as f32 + 2.0 * PADDING;
+    // Reste entièrement visible près des bords de la fenêtre
+    let x = position.0.min(screen.w - width).max(0.0);
+    let y = position.1.min(screen.h - height).max(0.0);
+
+    let panel = graphics::Mesh::new_rectangle(
+        ctx,
+        graphics::DrawMode::fill(),
+        Rect::new(x, y, width, height),
+        PANEL_COLOR,
+    )?;
+    graphics::draw(ctx, &panel, DrawParam::default())?;
+    graphics::draw(
+        ctx,
+        &text,
+        DrawParam::default()
+            .dest([x + PADDING, y + PADDING])
+            .color(TEXT_COLOR),
+    )
+}
@@ -2,19 +2,80 @@ use ggez::graphics::Color;
 use ggez::ContextBuilder;
 use ggez::{conf, event, GameResult};
 use std::cmp::Ordering;
+use std::fmt::Write;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
 
+mod agent;
+mod batch;
+mod brain;
+mod camera;
+mod cli;
+mod clock;
+mod daylight;
+mod editor;
+mod env;
+mod failures;
+mod game;
+mod generation;
+mod growth;
+mod hazards;
+mod hud;
 mod map;
+mod metrics;
+mod mission;
+mod overlay;
+mod render;
+mod replay;
 mod robot;
+mod scenario;
+mod station;
+mod terrain;
 
-use crate::map::Map;
+use crate::agent::{AgentLink, StandIn};
+use crate::batch::Sweep;
+use crate::cli::{Cli, Command, ReportFormat, USAGE};
+use crate::env::Env;
+use crate::game::Game;
+use crate::map::{DispatchMode, Map};
+use crate::mission::{MissionConfig, MissionOutcome};
+use crate::replay::{Playback, Recorder, Replay};
 use crate::robot::{RobotExplorer, RobotExtractor};
 
 const MAP_SIZE: usize = 10;
 const CELL_SIZE: f32 = 30.0;
+const MAX_WINDOW_SIZE: f32 = 800.0;
 
-const OBSTACLE_THRESHOLD: f64 = 0.2;
-const ENERGY_THRESHOLD: f64 = 0.5;
-const MINERALS_THRESHOLD: f64 = 0.5;
+const NB_EXPLORERS: usize = 2;
+const NB_EXTRACTORS: usize = 2;
+const MAX_MISSION_TICKS: usize = 10_000;
+const STALL_TICKS: usize = 500;
+
+// Coûts de déplacement : MIN_STEP_COST correspond à une case franchie par tick
+const MIN_STEP_COST: usize = 10;
+const ELEVATION_SCALE: f64 = 100.0; // mètres
+const SLOPE_COST: f64 = 1.0; // par mètre de montée
+const MAX_CLIMB: f64 = 25.0;
+const MAX_LOADED_CLIMB: f64 = 15.0;
+
+const BATTERY_CAPACITY: f32 = 100.0;
+const BATTERY_DRAIN_PER_COST: f32 = 0.05;
+const BATTERY_RECHARGE: f32 = 10.0;
+const LOW_BATTERY: f32 = 30.0;
+
+// Un robot dont la santé tombe à zéro est en panne jusqu'à sa réparation
+const MAX_HEALTH: f32 = 100.0;
+// Santé rendue par tick par un extracteur dépanneur ou par l'atelier de la station
+const REPAIR_RATE: f32 = 20.0;
+
+// Portée des capteurs des explorateurs, en cases
+const SENSOR_RANGE: usize = 1;
+
+const OBSTACLE_THRESHOLD: f64 = 0.3;
+const ENERGY_THRESHOLD: f64 = 1.1;
+const MINERALS_THRESHOLD: f64 = 1.1;
 // const SCIENCE_INTERESTS_THRESHOLD: f64 = 0.5;
 
 const DIRECTIONS: [(isize, isize); 8] = [
@@ -70,40 +131,397 @@ impl PartialOrd for Node {
     }
 }
 
-// Fonction de calcul de l'heuristique
+// Heuristique de A* : distance de Chebyshev, les diagonales coûtant autant qu'un pas droit.
+// Multipliée par MIN_STEP_COST, elle ne surestime jamais le coût restant.
 fn heuristic(x1: usize, y1: usize, x2: usize, y2: usize) -> usize {
-    ((x2 as isize - x1 as isize).abs() + (y1 as isize - y2 as isize).abs()) as usize
+    x1.abs_diff(x2).max(y1.abs_diff(y2))
 }
 
-fn main() -> GameResult {
-    let cb = ContextBuilder::new("CosmoBots", "Team CosmoBots")
+// Voisins d'une case dans les huit directions, sans sortir de la carte
+fn neighbors(x: usize, y: usize, size: usize) -> impl Iterator<Item = (usize, usize)> {
+    DIRECTIONS.iter().filter_map(move |&(dx, dy)| {
+        let nx = x as isize + dx;
+        let ny = y as isize + dy;
+        if nx < 0 || ny < 0 || nx >= size as isize || ny >= size as isize {
+            None
+        } else {
+            Some((nx as usize, ny as usize))
+        }
+    })
+}
+
+fn write_output(path: Option<&Path>, text: &str) {
+    match path {
+        Some(path) => {
+            if let Err(error) = std::fs::write(path, text) {
+                eprintln!("Could not write {}: {}", path.display(), error);
+                std::process::exit(1);
+            }
+            println!("Written to {}", path.display());
+        }
+        None => print!("{}", text),
+    }
+}
+
+fn exit_with(error: String) -> ! {
+    eprintln!("{}", error);
+    std::process::exit(1);
+}
+
+fn save_replay(recorder: &Recorder, path: &Path) {
+    match recorder.replay.save(path) {
+        Ok(()) => println!(
+            "Replay of {} ticks saved to {}",
+            recorder.replay.ticks.len(),
+            path.display()
+        ),
+        Err(error) => eprintln!("Could not save the replay: {}", error),
+    }
+}
+
+// Chemin d'enregistrement d'une mission parmi plusieurs : `mission.txt` devient `mission-3.txt`
+fn numbered_path(path: &Path, index: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, index, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, index),
+    };
+    path.with_file_name(name)
+}
+
+// Répertoires des sprites, par ordre de priorité : ggez cherche d'abord `resources/` à côté
+// de l'exécutable, puis viennent le répertoire courant et, en dernier recours, les sources
+fn resource_dirs() -> Vec<PathBuf> {
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    let roots = exe_dir
+        .into_iter()
+        .chain(std::env::current_dir().ok())
+        .chain(std::iter::once(PathBuf::from(env!("CARGO_MANIFEST_DIR"))));
+    roots
+        .flat_map(|root| [root.join("resources"), root.join("assets/textures")])
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+fn window(config: &MissionConfig) -> ContextBuilder {
+    let side = ((config.map_size as f32 * CELL_SIZE) + 1.0).min(MAX_WINDOW_SIZE);
+    resource_dirs()
+        .into_iter()
+        .fold(
+            ContextBuilder::new("CosmoBots", "Team CosmoBots"),
+            |builder, dir| builder.add_resource_path(dir),
+        )
         .window_setup(conf::WindowSetup::default().title("CosmoBots"))
-        .window_mode(conf::WindowMode::default().dimensions(
-            (MAP_SIZE as f32 * CELL_SIZE) + 1.0,
-            (MAP_SIZE as f32 * CELL_SIZE) + 1.0,
-        ));
+        .window_mode(
+            conf::WindowMode::default()
+                .dimensions(side, side)
+                .resizable(true),
+        )
+}
+
+fn run(cli: &Cli, seed: u64) -> GameResult {
+    let (mut map, report) = Map::generate(seed, &cli.settings, &cli.config);
+    map.dispatch_mode = cli.dispatch_mode;
+    println!("{}", report);
+
+    let (ctx, event_loop) = &mut window(&cli.config).build()?;
+    let mut game = Game::new(ctx, map);
+    if cli.record.is_some() {
+        game.clock.recorder = Some(Recorder::new(seed, cli.overrides.clone(), &game.map));
+    }
+    if let Some(options) = &cli.agent {
+        let mut agent = AgentLink::connect(options).unwrap_or_else(|error| exit_with(error));
+        agent.start(&game.map, seed);
+        game.clock.agent = Some(agent);
+    }
+    event::run(ctx, event_loop, &mut game)?;
 
-    let mut map = Map::new();
+    if let Some(agent) = &mut game.clock.agent {
+        agent.finish(&game.map);
+    }
+
+    if let (Some(path), Some(recorder)) = (&cli.record, &game.clock.recorder) {
+        save_replay(recorder, path);
+    }
+    Ok(())
+}
+
+// Missions sans fenêtre sur les graines seed, seed + 1, ...
+fn sim(cli: &Cli, seed: u64) {
+    let modes = if cli.compare {
+        vec![DispatchMode::Serialized, DispatchMode::Concurrent]
+    } else {
+        vec![cli.dispatch_mode]
+    };
+    let mut output = String::new();
+    let mut summaries = vec![];
+    let mut agent = cli
+        .agent
+        .as_ref()
+        .map(|options| AgentLink::connect(options).unwrap_or_else(|error| exit_with(error)));
 
-    // Place les ressources
-    map.place_resources();
+    for index in 0..cli.missions {
+        let seed = seed.wrapping_add(index as u64);
+        let (generated, _) = Map::generate(seed, &cli.settings, &cli.config);
+        for &mode in &modes {
+            let mut map = generated.clone();
+            map.dispatch_mode = mode;
+            map.verbose = !cli.quiet;
 
-    // Génère les emplacements des ressources
-    let resources: Vec<(usize, usize)> = map
-        .energy
+            let several = cli.missions > 1 || modes.len() > 1;
+            let mut recorder = cli
+                .record
+                .as_ref()
+                .map(|_| Recorder::new(seed, cli.overrides.clone(), &map));
+            if let Some(agent) = &mut agent {
+                agent.start(&map, seed);
+            }
+            while !map.game_over {
+                if let Some(agent) = &mut agent {
+                    agent.exchange(&mut map);
+                }
+                match &mut recorder {
+                    Some(recorder) => recorder.tick(&mut map),
+                    None => map.update_robot(),
+                }
+            }
+            if let Some(agent) = &mut agent {
+                agent.finish(&map);
+            }
+            if let (Some(path), Some(recorder)) = (&cli.record, &recorder) {
+                let path = if several {
+                    numbered_path(path, summaries.len())
+                } else {
+                    path.clone()
+                };
+                save_replay(recorder, &path);
+            }
+            let summary = map.summary();
+            if let Some(path) = &cli.metrics {
+                let path = if several {
+                    numbered_path(path, summaries.len())
+                } else {
+                    path.clone()
+                };
+                let json = serde_json::to_string_pretty(&map.metrics)
+                    .expect("metrics are always serializable");
+                if let Err(error) = std::fs::write(&path, json + "\n") {
+                    eprintln!("Could not write {}: {}", path.display(), error);
+                }
+            }
+            let _ = writeln!(output, "seed {} {:?}: {}", seed, mode, summary);
+            summaries.push(summary);
+        }
+    }
+
+    let complete = summaries
         .iter()
-        .enumerate()
-        .flat_map(|(y, row)| {
-            row.iter()
-                .enumerate()
-                .filter(|(_, &val)| val)
-                .map(move |(x, _)| (x, y))
-        })
-        .collect();
-
-    // Place les obstacles en évitant les emplacements des ressources
-    map.place_obstacles(&resources);
-
-    let (ctx, event_loop) = &mut cb.build()?;
-    event::run(ctx, event_loop, &mut map)
+        .filter(|s| s.outcome.is_some_and(MissionOutcome::is_success))
+        .count();
+    let mean_ticks =
+        summaries.iter().map(|s| s.ticks).sum::<usize>() as f32 / summaries.len() as f32;
+    let collected: usize = summaries.iter().map(|s| s.collected).sum();
+    let total_ticks = summaries.iter().map(|s| s.ticks).sum::<usize>().max(1);
+    let _ = writeln!(
+        output,
+        "{} missions: {} complete, {:.1} ticks on average, {} resources collected, {:.1} per 1000 ticks",
+        summaries.len(),
+        complete,
+        mean_ticks,
+        collected,
+        collected as f32 * 1000.0 / total_ticks as f32
+    );
+    write_output(cli.output.as_deref(), &output);
+}
+
+fn gen(cli: &Cli, seed: u64) {
+    let (map, report) = Map::generate(seed, &cli.settings, &cli.config);
+    println!("{}", report);
+    write_output(cli.output.as_deref(), &map.preview());
+}
+
+fn replay(cli: &Cli, path: &Path) -> GameResult {
+    let replay = Replay::load(path).unwrap_or_else(|error| exit_with(error));
+    if cli.validate {
+        match replay.validate() {
+            Ok(ticks) => println!("Replay valid: {} ticks re-simulated identically", ticks),
+            Err(error) => exit_with(format!("Replay invalid: {}", error)),
+        }
+        return Ok(());
+    }
+
+    let map = replay
+        .initial_map()
+        .unwrap_or_else(|error| exit_with(error));
+    let (ctx, event_loop) = &mut window(&map.config).build()?;
+    let mut game = Game::new(ctx, map);
+    game.clock.playback = Some(Playback::new(replay, &game.map));
+    event::run(ctx, event_loop, &mut game)
+}
+
+// Mesure le temps de génération et le débit de la simulation, journal coupé
+fn bench(cli: &Cli, seed: u64) {
+    let mut generation = Duration::ZERO;
+    let mut simulation = Duration::ZERO;
+    let mut ticks = 0;
+
+    for index in 0..cli.missions {
+        let start = Instant::now();
+        let (mut map, _) =
+            Map::generate(seed.wrapping_add(index as u64), &cli.settings, &cli.config);
+        generation += start.elapsed();
+
+        map.dispatch_mode = cli.dispatch_mode;
+        map.verbose = false;
+        let start = Instant::now();
+        ticks += map.run_headless().ticks;
+        simulation += start.elapsed();
+    }
+
+    let output = format!(
+        "{} missions on a {}x{} map\ngeneration: {:.2} ms per map\nsimulation: {} ticks in {:.2} s, {:.0} ticks/s\n",
+        cli.missions,
+        cli.config.map_size,
+        cli.config.map_size,
+        generation.as_secs_f64() * 1000.0 / cli.missions as f64,
+        ticks,
+        simulation.as_secs_f64(),
+        ticks as f64 / simulation.as_secs_f64().max(f64::EPSILON)
+    );
+    write_output(cli.output.as_deref(), &output);
+}
+
+// Balayage de paramètres en parallèle, rapport CSV ou JSON par configuration
+fn batch(cli: &Cli, seed: u64) {
+    let threads = cli.threads.unwrap_or_else(|| {
+        thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    });
+    let sweep = Sweep {
+        seeds: cli
+            .seeds
+            .clone()
+            .unwrap_or(seed..=seed.saturating_add(cli.missions as u64 - 1)),
+        strategies: if cli.strategies.is_empty() {
+            vec![cli.config.strategy]
+        } else {
+            cli.strategies.clone()
+        },
+        fleets: if cli.fleets.is_empty() {
+            vec![(cli.config.explorers, cli.config.extractors)]
+        } else {
+            cli.fleets.clone()
+        },
+        dispatch_modes: if cli.compare {
+            vec![DispatchMode::Serialized, DispatchMode::Concurrent]
+        } else {
+            vec![cli.dispatch_mode]
+        },
+        config: cli.config.clone(),
+        settings: cli.settings.clone(),
+        threads,
+    };
+
+    let start = Instant::now();
+    let reports = sweep.run();
+    let missions: usize = reports.iter().map(|report| report.runs).sum();
+    eprintln!(
+        "{} missions in {} configurations on {} threads, {:.1} s",
+        missions,
+        reports.len(),
+        threads,
+        start.elapsed().as_secs_f64()
+    );
+
+    let json = cli.format == Some(ReportFormat::Json)
+        || (cli.format.is_none()
+            && cli
+                .output
+                .as_deref()
+                .is_some_and(|path| path.extension().is_some_and(|e| e == "json")));
+    let report = if json {
+        batch::to_json(&reports)
+    } else {
+        batch::to_csv(&reports)
+    };
+    write_output(cli.output.as_deref(), &report);
+}
+
+// Client de démonstration du protocole, sur l'entrée et la sortie standard ou en TCP
+fn agent(cli: &Cli) {
+    let result = match &cli.connect {
+        Some(address) => TcpStream::connect(address)
+            .and_then(|stream| Ok((stream.try_clone()?, stream)))
+            .map_err(|error| format!("{}: {}", address, error))
+            .and_then(|(reader, writer)| StandIn::serve(reader, writer, cli.delay)),
+        None => StandIn::serve(std::io::stdin(), std::io::stdout(), cli.delay),
+    };
+    match result {
+        Ok(missions) => eprintln!("Stand-in agent played {} missions", missions),
+        Err(error) => exit_with(error),
+    }
+}
+
+// Environnement d'apprentissage piloté par l'entrée standard, réponses sur la sortie standard
+fn gym(cli: &Cli, seed: u64) {
+    let mut env = Env::new(
+        cli.config.clone(),
+        cli.settings.clone(),
+        cli.encoding.clone(),
+        cli.rewards,
+    );
+    env.dispatch_mode = cli.dispatch_mode;
+    match env.serve(seed, std::io::stdin(), std::io::stdout()) {
+        Ok(episodes) => eprintln!("Served {} episodes", episodes),
+        Err(error) => exit_with(error),
+    }
+}
+
+fn main() -> GameResult {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cli = Cli::parse(&args)
+        .unwrap_or_else(|error| exit_with(format!("{}\nRun `cosmobots --help` for usage.", error)));
+    if cli.help {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    if let Some(scenario) = &cli.scenario {
+        println!("Scenario: {}", scenario.name);
+        if let Some(description) = &scenario.description {
+            println!("{}", description);
+        }
+    }
+    let seed = cli.seed.unwrap_or_else(rand::random);
+    match &cli.command {
+        Command::Run => run(&cli, seed),
+        Command::Sim => {
+            sim(&cli, seed);
+            Ok(())
+        }
+        Command::Gen => {
+            gen(&cli, seed);
+            Ok(())
+        }
+        Command::Replay(path) => replay(&cli, path),
+        Command::Bench => {
+            bench(&cli, seed);
+            Ok(())
+        }
+        Command::Batch => {
+            batch(&cli, seed);
+            Ok(())
+        }
+        Command::Agent => {
+            agent(&cli);
+            Ok(())
+        }
+        Command::Gym => {
+            gym(&cli, seed);
+            Ok(())
+        }
+    }
 }
@@ -1,115 +1,251 @@
-use ggez::graphics;
-use ggez::{event, timer, Context, GameResult};
-use noise::{Fbm, NoiseFn, Perlin};
-use rand::Rng;
+use noise::NoiseFn;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+use std::collections::VecDeque;
+use std::fmt;
 use std::time::Duration;
 
+use crate::brain::Action;
+use crate::camera::RobotRef;
+use crate::hazards::ActiveEvent;
+use crate::metrics::{Metrics, RobotSample, SearchStats};
+use crate::mission::{ExplorerStrategy, MissionConfig, MissionOutcome, MissionSummary, Victory};
+use crate::neighbors;
+use crate::robot::find_path;
+use crate::station::{ResourceKind, Station};
+use crate::terrain::{sample01, Biome, GenerationSettings};
 use crate::{RobotExplorer, RobotExtractor};
-use crate::{CELL_SIZE, ENERGY_THRESHOLD, MAP_SIZE, MINERALS_THRESHOLD, OBSTACLE_THRESHOLD};
-use crate::{
-    DEFAULT_COLOR, ENERGY_COLOR, FOG_COLOR, MINERALS_COLOR, OBSTACLE_COLOR, ROBOT_EXPLORER_COLOR,
-    ROBOT_EXTRACTOR_COLOR, SCIENCE_INTERESTS_COLOR, STATION_COLOR,
-};
+use crate::{BATTERY_DRAIN_PER_COST, ELEVATION_SCALE, MIN_STEP_COST, SLOPE_COST};
 
+// Mode de coordination entre explorateurs et extracteurs
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DispatchMode {
+    // Un seul rôle se déplace à la fois (comportement historique)
+    Serialized,
+    // Les explorateurs continuent l'exploration pendant l'extraction
+    Concurrent,
+}
+
+// Niveaux de visibilité d'une case
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Visibility {
+    // Jamais observée
+    Unknown,
+    // Observée auparavant : son contenu mémorisé peut être périmé
+    Remembered,
+    // Dans la portée des capteurs d'un explorateur
+    Visible,
+}
+
+// Grille de cases indexée par [y][x]
+pub type Grid<T> = Vec<Vec<T>>;
+
+pub fn grid<T: Clone>(size: usize, value: T) -> Grid<T> {
+    vec![vec![value; size]; size]
+}
+
+// Au-delà de ce nombre de cases modifiées, toute la carte est considérée comme modifiée ;
+// la liste reste ainsi bornée dans les simulations sans affichage
+const MAX_DAMAGED_CELLS: usize = 4096;
+
+// Cases dont l'apparence a pu changer depuis le dernier rendu
+#[derive(Clone, Default)]
+pub struct Damage {
+    pub all: bool,
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl Damage {
+    pub fn everything() -> Self {
+        Damage {
+            all: true,
+            cells: vec![],
+        }
+    }
+
+    // Cases relevées depuis le dernier appel, la liste repart vide
+    pub fn take(&mut self) -> Damage {
+        std::mem::take(self)
+    }
+
+    pub fn mark(&mut self, x: usize, y: usize) {
+        if self.all {
+            return;
+        }
+        if self.cells.len() >= MAX_DAMAGED_CELLS {
+            *self = Damage::everything();
+            return;
+        }
+        self.cells.push((x, y));
+    }
+}
+
+// Accessibilité de la station, recalculée une fois par tick et après chaque modification
+#[derive(Clone)]
+pub struct Reachability {
+    // Cases atteignables depuis la station
+    pub outbound: Grid<bool>,
+    // Cases d'où un robot peut encore regagner la station
+    pub homebound: Grid<bool>,
+    // Cases d'où un extracteur chargé peut regagner la station
+    pub loaded_homebound: Grid<bool>,
+}
+
+impl Reachability {
+    fn new(size: usize) -> Self {
+        Reachability {
+            outbound: grid(size, false),
+            homebound: grid(size, false),
+            loaded_homebound: grid(size, false),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Map {
-    pub energy: [[bool; MAP_SIZE]; MAP_SIZE],
-    pub minerals: [[bool; MAP_SIZE]; MAP_SIZE],
-    pub science_interests: [[bool; MAP_SIZE]; MAP_SIZE],
-    pub obstacles: [[bool; MAP_SIZE]; MAP_SIZE],
-    pub explored: [[bool; MAP_SIZE]; MAP_SIZE],
-    pub fog_of_war: [[bool; MAP_SIZE]; MAP_SIZE],
-    pub robot_explorer: Option<RobotExplorer>,
-    pub robot_extractor: Option<RobotExtractor>,
+    pub size: usize,
+    pub config: MissionConfig,
+    pub energy: Grid<bool>,
+    pub minerals: Grid<bool>,
+    pub science_interests: Grid<bool>,
+    pub obstacles: Grid<bool>,
+    pub explored: Grid<bool>,
+    pub fog_of_war: Grid<bool>,
+    pub in_sensor_range: Grid<bool>,
+    // Ressources telles qu'observées lors du dernier passage des capteurs
+    pub seen_energy: Grid<bool>,
+    pub seen_minerals: Grid<bool>,
+    pub elevation: Grid<f64>,
+    pub moisture: Grid<f64>,
+    pub biomes: Grid<Biome>,
+    pub robot_explorers: Vec<RobotExplorer>,
+    pub robot_extractors: Vec<RobotExtractor>,
+    pub station: Station,
+    pub dispatch_mode: DispatchMode,
+    // Affiche le journal des événements de la mission sur la sortie standard
+    pub verbose: bool,
+    // Conserve les traces A* des robots pour le calque de débogage
+    pub trace_searches: bool,
+    pub rng: Pcg64,
     pub update_timer: Duration,
     pub game_over: bool,
-    pub nb_consumables: usize,
+    pub outcome: Option<MissionOutcome>,
+    pub tick: usize,
+    pub collected: usize,
+    pub last_progress: (usize, usize, usize, usize),
+    pub last_progress_tick: usize,
     pub resources: Vec<(usize, usize)>,
+    pub metrics: Metrics,
+    // Ordres d'un agent externe pour le prochain tick
+    pub orders: Vec<(RobotRef, Action)>,
+    // Tempêtes et zones dangereuses en cours
+    pub events: Vec<ActiveEvent>,
+    // Gisements récoltés en attente de repousse, par tick de récolte
+    pub depleted: VecDeque<(usize, (usize, usize), ResourceKind)>,
+    // Graine du bruit d'apparition des gisements, tirée à la première apparition
+    pub spawn_seed: Option<u32>,
+    pub reachability: Reachability,
+    // Relevé par la couche de rendu à chaque image
+    pub damage: Damage,
 }
 
 impl Map {
-    pub fn new() -> Self {
-        let mut map = Map {
-            energy: [[false; MAP_SIZE]; MAP_SIZE],
-            minerals: [[false; MAP_SIZE]; MAP_SIZE],
-            science_interests: [[false; MAP_SIZE]; MAP_SIZE],
-            obstacles: [[false; MAP_SIZE]; MAP_SIZE],
-            explored: [[false; MAP_SIZE]; MAP_SIZE],
-            fog_of_war: [[true; MAP_SIZE]; MAP_SIZE],
-            robot_explorer: None,
-            robot_extractor: None,
+    pub fn with_seed(seed: u64, config: &MissionConfig) -> Self {
+        let size = config.map_size;
+        Map {
+            size,
+            config: config.clone(),
+            energy: grid(size, false),
+            minerals: grid(size, false),
+            science_interests: grid(size, false),
+            obstacles: grid(size, false),
+            explored: grid(size, false),
+            fog_of_war: grid(size, true),
+            in_sensor_range: grid(size, false),
+            seen_energy: grid(size, false),
+            seen_minerals: grid(size, false),
+            elevation: grid(size, 0.5),
+            moisture: grid(size, 0.5),
+            biomes: grid(size, Biome::Plains),
+            robot_explorers: vec![],
+            robot_extractors: vec![],
+            station: Station::new(0, 0),
+            dispatch_mode: DispatchMode::Concurrent,
+            verbose: true,
+            trace_searches: false,
+            rng: Pcg64::seed_from_u64(seed),
             update_timer: Duration::from_secs(1),
             game_over: false,
-            nb_consumables: 0,
+            outcome: None,
+            tick: 0,
+            collected: 0,
+            last_progress: (0, 0, 0, 0),
+            last_progress_tick: 0,
+            metrics: Metrics::default(),
             resources: vec![],
-        };
-
-        if let Some((x, y)) = map.init_robot_position() {
-            map.robot_explorer = Some(RobotExplorer::new(x, y));
-            map.robot_extractor = Some(RobotExtractor::new(x, y));
-            map.fog_of_war[y][x] = false;
+            orders: vec![],
+            events: vec![],
+            depleted: VecDeque::new(),
+            spawn_seed: None,
+            reachability: Reachability::new(size),
+            damage: Damage::everything(),
         }
-
-        map
     }
 
-    pub fn init_robot_position(&self) -> Option<(usize, usize)> {
-        let mut rng = rand::thread_rng();
-        let mut attempts = 0;
-        const MAX_ATTEMPTS: usize = 100;
-
-        loop {
-            let x = rng.gen_range(0..MAP_SIZE);
-            let y = rng.gen_range(0..MAP_SIZE);
+    pub fn log(&self, message: fmt::Arguments) {
+        if self.verbose {
+            println!("{}", message);
+        }
+    }
 
-            if !self.obstacles[y][x] && !self.energy[y][x] && !self.minerals[y][x] {
-                return Some((x, y));
-            }
+    // Calcule les couches d'altitude et d'humidité puis le biome de chaque case
+    pub fn place_terrain(&mut self, settings: &GenerationSettings) {
+        let fbm_elevation = settings.elevation.build(self.rng.gen());
+        let fbm_moisture = settings.moisture.build(self.rng.gen());
 
-            attempts += 1;
-            if attempts >= MAX_ATTEMPTS {
-                return None;
+        for y in 0..self.size {
+            for x in 0..self.size {
+                self.elevation[y][x] = sample01(&fbm_elevation, x, y);
+                self.moisture[y][x] = sample01(&fbm_moisture, x, y);
+                self.biomes[y][x] = Biome::classify(self.elevation[y][x], self.moisture[y][x]);
             }
         }
     }
 
-    pub fn place_obstacles(&mut self, _resources: &[(usize, usize)]) {
-        let mut rng = rand::thread_rng();
-        let seed = rng.gen();
-        let fbm_obstacles = Fbm::<Perlin>::new(seed);
+    pub fn place_obstacles(&mut self, settings: &GenerationSettings) {
+        let fbm_obstacles = settings.obstacles.build(self.rng.gen());
 
-        for y in 0..MAP_SIZE {
-            for x in 0..MAP_SIZE {
+        for y in 0..self.size {
+            for x in 0..self.size {
                 if !self
                     .resources
                     .iter()
                     .any(|&(res_x, res_y)| res_x == x && res_y == y)
                 {
                     let obstacles_noise = fbm_obstacles.get([x as f64, y as f64]);
-                    self.obstacles[y][x] = obstacles_noise > OBSTACLE_THRESHOLD;
+                    let threshold = settings.obstacle_threshold - self.biomes[y][x].obstacle_bias();
+                    self.obstacles[y][x] = obstacles_noise > threshold;
                 }
             }
         }
     }
 
-    pub fn place_resources(&mut self) {
-        let mut rng = rand::thread_rng();
-        let energy_seed = rng.gen();
-        let minerals_seed = rng.gen();
-        let fbm_energy = Fbm::<Perlin>::new(energy_seed);
-        let fbm_minerals = Fbm::<Perlin>::new(minerals_seed);
+    pub fn place_resources(&mut self, settings: &GenerationSettings) {
+        let fbm_energy = settings.resources.build(self.rng.gen());
+        let fbm_minerals = settings.resources.build(self.rng.gen());
 
-        for y in 0..MAP_SIZE {
-            for x in 0..MAP_SIZE {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let biome = self.biomes[y][x];
                 let energy_noise = fbm_energy.get([x as f64, y as f64]);
                 let minerals_noise = fbm_minerals.get([x as f64, y as f64]);
-                self.energy[y][x] = energy_noise > ENERGY_THRESHOLD;
-                self.minerals[y][x] = minerals_noise > MINERALS_THRESHOLD;
+                self.energy[y][x] = energy_noise > settings.energy_threshold - biome.energy_bias();
+                self.minerals[y][x] = !self.energy[y][x]
+                    && minerals_noise > settings.minerals_threshold - biome.minerals_bias();
 
                 if self.energy[y][x] || self.minerals[y][x] {
                     self.resources.push((x, y));
                 }
-
-                println!("Number of consumables: {}", self.resources.len());
             }
         }
     }
@@ -118,112 +254,613 @@ impl Map {
         if self.game_over {
             return;
         }
+        self.tick += 1;
+        self.update_hazards();
+        self.update_failures();
+        self.update_growth();
+        // Éboulements, repousses et cargaisons lâchées ont pu modifier la carte
+        self.refresh_reachability();
+        let before = self.robot_samples();
+
+        // Les scripts voient tous la carte connue au début du tick
+        let scripts = self.config.scripts.clone();
+        let view = (!scripts.is_empty()).then(|| self.knowledge());
+
+        let mut extractors = std::mem::take(&mut self.robot_extractors);
+        for (index, extractor) in extractors.iter_mut().enumerate() {
+            // La trace ne décrit que la recherche du tick courant
+            extractor.last_search = None;
+            if extractor.broken {
+                continue;
+            }
+            // Un dépannage passe avant les ordres, le script et les extractions
+            self.assign_rescue(index, extractor);
+            if extractor.rescue.is_some() {
+                self.rescue_trip(extractor);
+                continue;
+            }
+            match self.pilot(RobotRef::Extractor(index), &scripts.extractor, &view) {
+                Some(pilot) => self.directed_extractor(pilot, index, extractor),
+                None => self.update_extractor(extractor),
+            }
+        }
+        self.robot_extractors = extractors;
+
+        // En mode sérialisé, les explorateurs attendent la fin des extractions
+        let extractors_busy = self.robot_extractors.iter().any(|e| e.is_busy());
+        if self.dispatch_mode == DispatchMode::Concurrent || !extractors_busy {
+            let exhausted = self.count_reachable_consumables() == 0;
+            let mut explorers = std::mem::take(&mut self.robot_explorers);
+            for (index, explorer) in explorers.iter_mut().enumerate() {
+                explorer.last_search = None;
+                explorer.frontier.clear();
+                if explorer.broken {
+                    continue;
+                }
+                match self.pilot(RobotRef::Explorer(index), &scripts.explorer, &view) {
+                    Some(pilot) => self.directed_explorer(pilot, index, explorer),
+                    None => self.update_explorer(explorer, exhausted),
+                }
+            }
+            self.robot_explorers = explorers;
+        }
+
+        self.repair_robots();
+
+        // Les ordres des robots qui n'ont pas joué ne valent que pour ce tick
+        self.orders.clear();
+        self.update_visibility();
+        self.update_metrics(&before);
+
+        if let Some(outcome) = self.check_mission_outcome() {
+            self.game_over = true;
+            self.outcome = Some(outcome);
+            self.log(format_args!("Game Over: {}", self.summary()));
+            self.log(format_args!("{}", self.metrics));
+        }
+    }
+
+    // État de chaque robot pour les métriques, explorateurs puis extracteurs
+    fn robot_samples(&self) -> Vec<RobotSample> {
+        let capacity = self.config.robots.battery_capacity;
+        let explorers = self.robot_explorers.iter().map(|e| RobotSample {
+            position: (e.x, e.y),
+            at_station: e.is_at_station(),
+            full_battery: e.battery >= capacity,
+            carrying: false,
+        });
+        let extractors = self.robot_extractors.iter().map(|e| RobotSample {
+            position: (e.x, e.y),
+            at_station: e.is_at_station(),
+            full_battery: e.battery >= capacity,
+            carrying: e.cargo.is_some(),
+        });
+        explorers.chain(extractors).collect()
+    }
+
+    fn update_metrics(&mut self, before: &[RobotSample]) {
+        let after = self.robot_samples();
+        self.metrics.record_tick(before, &after);
+
+        let explorers: Vec<SearchStats> = self
+            .robot_explorers
+            .iter_mut()
+            .map(|e| std::mem::take(&mut e.searches))
+            .collect();
+        let extractors: Vec<SearchStats> = self
+            .robot_extractors
+            .iter_mut()
+            .map(|e| std::mem::take(&mut e.searches))
+            .collect();
+        self.metrics.record_searches(&explorers, &extractors);
+
+        let discovered = self
+            .fog_of_war
+            .iter()
+            .flatten()
+            .filter(|&&fog| !fog)
+            .count();
+        self.metrics
+            .coverage
+            .push(discovered as f32 * 100.0 / (self.size * self.size) as f32);
+        if self.metrics.first_discovery.is_none()
+            && self
+                .robot_explorers
+                .iter()
+                .any(|e| e.resource_position.is_some())
+        {
+            self.metrics.first_discovery = Some(self.tick);
+        }
+        if self.metrics.first_delivery.is_none() && self.collected > 0 {
+            self.metrics.first_delivery = Some(self.tick);
+        }
+    }
+
+    // Évalue les conditions de fin de mission après chaque tick
+    fn check_mission_outcome(&mut self) -> Option<MissionOutcome> {
+        let progress = (
+            self.explored.iter().flatten().filter(|&&val| val).count(),
+            self.fog_of_war
+                .iter()
+                .flatten()
+                .filter(|&&val| !val)
+                .count(),
+            self.count_consumables(),
+            self.collected,
+        );
+        if progress != self.last_progress {
+            self.last_progress = progress;
+            self.last_progress_tick = self.tick;
+        } else if self.is_night() {
+            // Les explorateurs attendent l'aube à la station : la nuit ne compte pas comme un blocage
+            self.last_progress_tick += 1;
+        }
+
+        // Un robot en panne ne reviendra pas de lui-même
+        let robots_home = self
+            .robot_explorers
+            .iter()
+            .all(|e| e.broken || e.is_at_station() || self.is_stranded(e.x, e.y, e.battery))
+            && self.robot_extractors.iter().all(|e| {
+                e.cargo.is_none()
+                    && (e.broken || e.is_at_station() || self.is_stranded(e.x, e.y, e.battery))
+            });
+        let all_stranded = self
+            .robot_explorers
+            .iter()
+            .map(|e| (e.x, e.y, e.battery, e.broken))
+            .chain(
+                self.robot_extractors
+                    .iter()
+                    .map(|e| (e.x, e.y, e.battery, e.broken)),
+            )
+            .all(|(x, y, battery, broken)| broken || self.is_stranded(x, y, battery));
+
+        let exhausted = self.count_reachable_consumables() == 0 && robots_home;
+
+        match self.config.victory {
+            Victory::AllResources if exhausted => {
+                return Some(MissionOutcome::AllResourcesCollected)
+            }
+            Victory::Stockpile { energy, minerals }
+                if self.station.energy >= energy && self.station.minerals >= minerals =>
+            {
+                return Some(MissionOutcome::StockpileReached)
+            }
+            Victory::Stockpile { .. } if exhausted => {
+                return Some(MissionOutcome::ResourcesExhausted)
+            }
+            _ => {}
+        }
+
+        // Une colonie tourne jusqu'au bout de son budget, même pendant les accalmies
+        let endless = self.config.victory == Victory::Endless;
+        if all_stranded {
+            Some(MissionOutcome::AllRobotsStranded)
+        } else if !endless && self.tick - self.last_progress_tick >= self.config.stall_ticks {
+            Some(MissionOutcome::NoProgress)
+        } else if self.tick >= self.config.max_ticks && endless {
+            Some(MissionOutcome::ColonySustained)
+        } else if self.tick >= self.config.max_ticks {
+            Some(MissionOutcome::TickBudgetExhausted)
+        } else {
+            None
+        }
+    }
+
+    // Recalcule les cases couvertes par les capteurs et mémorise leur contenu
+    pub fn update_visibility(&mut self) {
+        // Les cases entrées ou sorties de la portée des capteurs changent d'apparence
+        let previous = (!self.damage.all).then(|| self.in_sensor_range.clone());
+        for row in self.in_sensor_range.iter_mut() {
+            row.fill(false);
+        }
+        // Seuls les explorateurs lèvent le brouillard
+        let sensors: Vec<(usize, usize)> =
+            self.robot_explorers.iter().map(|e| (e.x, e.y)).collect();
+
+        for (sx, sy) in sensors {
+            let range = self.sensor_range_at(sx, sy);
+            let (x0, y0) = (sx.saturating_sub(range), sy.saturating_sub(range));
+            let x1 = (sx + range).min(self.size - 1);
+            let y1 = (sy + range).min(self.size - 1);
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    self.in_sensor_range[y][x] = true;
+                    self.fog_of_war[y][x] = false;
+                    self.seen_energy[y][x] = self.energy[y][x];
+                    self.seen_minerals[y][x] = self.minerals[y][x];
+                }
+            }
+        }
+
+        if let Some(previous) = previous {
+            for (y, (before, after)) in previous.iter().zip(&self.in_sensor_range).enumerate() {
+                for (x, _) in before
+                    .iter()
+                    .zip(after)
+                    .enumerate()
+                    .filter(|(_, (b, a))| b != a)
+                {
+                    self.damage.mark(x, y);
+                }
+            }
+        }
+    }
+
+    pub fn visibility(&self, x: usize, y: usize) -> Visibility {
+        if self.in_sensor_range[y][x] {
+            Visibility::Visible
+        } else if self.fog_of_war[y][x] {
+            Visibility::Unknown
+        } else {
+            Visibility::Remembered
+        }
+    }
 
-        if let Some(mut extractor) = self.robot_extractor.take() {
-            if !extractor.waiting {
-                if extractor.carrying_resource {
-                    if let Some(path) = extractor.move_towards(
-                        extractor.station_x,
-                        extractor.station_y,
-                        &self.obstacles,
-                        &self.fog_of_war,
-                    ) {
-                        if path.len() > 1 {
-                            let (next_x, next_y) = path[1];
-                            extractor.x = next_x;
-                            extractor.y = next_y;
-
-                            if extractor.x == extractor.station_x
-                                && extractor.y == extractor.station_y
-                            {
-                                extractor.carrying_resource = false;
-                                extractor.waiting = true;
-                                if let Some(mut explorer) = self.robot_explorer.as_mut() {
-                                    explorer.waiting = false;
-                                }
-                                println!("Extractor returned to the station with resource. Remaining resources: {}", self.count_consumables());
-                            }
-                        }
-                    }
-                } else if let Some((target_x, target_y)) = extractor.target_position {
-                    if let Some(path) = extractor.move_towards(
-                        target_x,
-                        target_y,
-                        &self.obstacles,
-                        &self.fog_of_war,
-                    ) {
-                        if path.len() > 1 {
-                            let (next_x, next_y) = path[1];
-                            extractor.x = next_x;
-                            extractor.y = next_y;
-
-                            if extractor.x == target_x && extractor.y == target_y {
-                                extractor.collect_resource();
-                                self.energy[target_y][target_x] = false;
-                                self.minerals[target_y][target_x] = false;
-                            }
-                        }
-                    }
+    pub fn summary(&self) -> MissionSummary {
+        let remaining = self.count_consumables();
+        let explored = self
+            .fog_of_war
+            .iter()
+            .flatten()
+            .filter(|&&val| !val)
+            .count();
+        MissionSummary {
+            outcome: self.outcome,
+            ticks: self.tick,
+            collected: self.collected,
+            remaining,
+            unreachable: remaining - self.count_reachable_consumables(),
+            explored_percent: explored as f32 * 100.0 / (self.size * self.size) as f32,
+            distance: self.metrics.distance(),
+            idle_ticks: self.metrics.idle_ticks(),
+            throughput: self.throughput(),
+        }
+    }
+
+    // Un robot est bloqué hors de la station si sa batterie est vide
+    // ou s'il ne peut plus la regagner
+    pub fn is_stranded(&self, x: usize, y: usize, battery: f32) -> bool {
+        (x, y) != (self.station.x, self.station.y)
+            && (battery <= 0.0 || !self.reachability.homebound[y][x])
+    }
+
+    pub fn refresh_reachability(&mut self) {
+        self.reachability = Reachability {
+            outbound: self.reachable_cells(),
+            homebound: self.cells_reaching_station(false),
+            loaded_homebound: self.cells_reaching_station(true),
+        };
+    }
+
+    // Cases accessibles depuis la station sans obstacle ni pente infranchissable
+    pub fn reachable_cells(&self) -> Grid<bool> {
+        let mut reachable = grid(self.size, false);
+        let mut stack = vec![(self.station.x, self.station.y)];
+        reachable[self.station.y][self.station.x] = true;
+
+        while let Some((x, y)) = stack.pop() {
+            for (nx, ny) in neighbors(x, y, self.size) {
+                if !reachable[ny][nx] && self.step_cost((x, y), (nx, ny), false).is_some() {
+                    reachable[ny][nx] = true;
+                    stack.push((nx, ny));
                 }
             }
-            self.robot_extractor = Some(extractor);
-        }
-
-        if let Some(mut explorer) = self.robot_explorer.take() {
-            if (!explorer.waiting) {
-                let mut rng = rand::thread_rng();
-
-                let all_resources_collected = self.count_consumables() == 0 || self.is_map_empty();
-
-                if all_resources_collected {
-                    if let Some(path) =
-                        explorer.return_to_station(&self.obstacles, &self.fog_of_war)
-                    {
-                        if path.len() > 1 {
-                            let (next_x, next_y) = path[1];
-                            explorer.x = next_x;
-                            explorer.y = next_y;
-
-                            if explorer.x == explorer.station_x && explorer.y == explorer.station_y
-                            {
-                                self.game_over = true;
-                                println!("Game Over: Robot returned to the station. All resources have been collected.");
-                            }
-                        }
-                    }
-                } else if explorer.founded_resource {
-                    if let Some((resource_x, resource_y)) = explorer.resource_position {
-                        if explorer.x == explorer.station_x && explorer.y == explorer.station_y {
-                            self.robot_extractor.as_mut().unwrap().target_position =
-                                Some((resource_x, resource_y));
-                            explorer.founded_resource = false;
-                            explorer.resource_position = None;
-                            explorer.waiting = true;
-                            if let Some(mut extractor) = self.robot_extractor.as_mut() {
-                                extractor.waiting = false;
-                            }
-                            println!("Explorer returned to the station and provided resource position to Extractor.");
-                        } else if let Some(path) =
-                            explorer.return_to_station(&self.obstacles, &self.fog_of_war)
-                        {
-                            if path.len() > 1 {
-                                let (next_x, next_y) = path[1];
-                                explorer.x = next_x;
-                                explorer.y = next_y;
-                            }
-                        }
-                    }
-                } else {
-                    explorer.move_random(&mut rng, self);
+        }
+
+        reachable
+    }
+
+    // Cases d'où la station est atteignable, en remontant les déplacements praticables
+    fn cells_reaching_station(&self, loaded: bool) -> Grid<bool> {
+        let mut reaching = grid(self.size, false);
+        let mut stack = vec![(self.station.x, self.station.y)];
+        reaching[self.station.y][self.station.x] = true;
+
+        while let Some((x, y)) = stack.pop() {
+            for (nx, ny) in neighbors(x, y, self.size) {
+                if !reaching[ny][nx]
+                    && !self.obstacles[ny][nx]
+                    && self.step_cost((nx, ny), (x, y), loaded).is_some()
+                {
+                    reaching[ny][nx] = true;
+                    stack.push((nx, ny));
                 }
             }
-            self.robot_explorer = Some(explorer);
         }
+
+        reaching
     }
 
-    pub fn is_map_empty(&self) -> bool {
-        !self.energy.iter().any(|row| row.iter().any(|&val| val))
-            && !self.minerals.iter().any(|row| row.iter().any(|&val| val))
+    // Gisements qu'un extracteur peut atteindre à vide puis rapporter chargé,
+    // comme l'exige l'attribution des sites par la station
+    pub fn count_reachable_consumables(&self) -> usize {
+        let reachability = &self.reachability;
+        (0..self.size)
+            .flat_map(|y| (0..self.size).map(move |x| (x, y)))
+            .filter(|&(x, y)| {
+                reachability.outbound[y][x]
+                    && reachability.loaded_homebound[y][x]
+                    && (self.energy[y][x] || self.minerals[y][x])
+            })
+            .count()
+    }
+
+    // Coût de traversée vers une case voisine, None si elle est infranchissable
+    pub fn step_cost(
+        &self,
+        from: (usize, usize),
+        to: (usize, usize),
+        loaded: bool,
+    ) -> Option<usize> {
+        if self.obstacles[to.1][to.0] {
+            return None;
+        }
+
+        let climb = (self.elevation[to.1][to.0] - self.elevation[from.1][from.0]) * ELEVATION_SCALE;
+        let robots = &self.config.robots;
+        let max_climb = if loaded {
+            robots.max_loaded_climb
+        } else {
+            robots.max_climb
+        };
+        if climb > max_climb {
+            return None;
+        }
+
+        let slope_cost = (climb.max(0.0) * SLOPE_COST).round() as usize;
+        Some(self.biomes[to.1][to.0].base_cost() + slope_cost)
+    }
+
+    // Luminosité du relief selon l'altitude et la pente face au nord-ouest
+    pub fn hillshade(&self, x: usize, y: usize) -> f32 {
+        const HILLSHADE_STRENGTH: f64 = 4.0;
+
+        let elevation = self.elevation[y][x];
+        let upslope = if x > 0 && y > 0 {
+            elevation - self.elevation[y - 1][x - 1]
+        } else {
+            0.0
+        };
+        (0.55 + 0.45 * elevation + upslope * HILLSHADE_STRENGTH).clamp(0.25, 1.0) as f32
+    }
+
+    // Avance d'une case le long du chemin une fois l'effort accumulé suffisant
+    pub fn step_along(
+        &self,
+        path: &[(usize, usize)],
+        loaded: bool,
+        move_progress: &mut usize,
+        battery: &mut f32,
+    ) -> Option<(usize, usize)> {
+        if path.len() < 2 || *battery <= 0.0 {
+            return None;
+        }
+
+        let cost = self.step_cost(path[0], path[1], loaded)?;
+        *move_progress += MIN_STEP_COST;
+        if *move_progress < cost {
+            return None;
+        }
+
+        *move_progress = 0;
+        *battery = (*battery - cost as f32 * BATTERY_DRAIN_PER_COST).max(0.0);
+        Some(path[1])
+    }
+
+    pub(crate) fn recharge_extractor(&self, extractor: &mut RobotExtractor) {
+        let robots = &self.config.robots;
+        if extractor.is_at_station() {
+            extractor.battery =
+                (extractor.battery + self.recharge_rate()).min(robots.battery_capacity);
+        }
+    }
+
+    fn update_extractor(&mut self, extractor: &mut RobotExtractor) {
+        self.recharge_extractor(extractor);
+
+        if extractor.cargo.is_some() {
+            if let Some(path) =
+                extractor.move_towards(extractor.station_x, extractor.station_y, self)
+            {
+                if let Some((next_x, next_y)) = self.step_along(
+                    &path,
+                    true,
+                    &mut extractor.move_progress,
+                    &mut extractor.battery,
+                ) {
+                    extractor.x = next_x;
+                    extractor.y = next_y;
+                    self.unload(extractor);
+                }
+            }
+        } else if let Some((target_x, target_y)) = extractor.target_position {
+            if let Some(path) = extractor.move_towards(target_x, target_y, self) {
+                if let Some((next_x, next_y)) = self.step_along(
+                    &path,
+                    false,
+                    &mut extractor.move_progress,
+                    &mut extractor.battery,
+                ) {
+                    extractor.x = next_x;
+                    extractor.y = next_y;
+                }
+
+                if extractor.x == target_x && extractor.y == target_y {
+                    self.harvest(extractor);
+                }
+            } else {
+                // Site devenu inaccessible : il retourne dans la file de la station
+                self.station.pending_sites.push_back((target_x, target_y));
+                extractor.target_position = None;
+            }
+        } else if !extractor.is_at_station() {
+            if let Some(path) =
+                extractor.move_towards(extractor.station_x, extractor.station_y, self)
+            {
+                if let Some((next_x, next_y)) = self.step_along(
+                    &path,
+                    false,
+                    &mut extractor.move_progress,
+                    &mut extractor.battery,
+                ) {
+                    extractor.x = next_x;
+                    extractor.y = next_y;
+                }
+            }
+        } else {
+            self.assign_site(extractor);
+        }
+    }
+
+    // Attribue un site en attente à un extracteur libre, rechargé, à la station
+    pub(crate) fn assign_site(&mut self, extractor: &mut RobotExtractor) {
+        if extractor.is_busy()
+            || !extractor.is_at_station()
+            || extractor.battery < self.config.robots.battery_capacity
+        {
+            return;
+        }
+        // Ne retient que les sites dont le retour chargé est praticable
+        let station = (self.station.x, self.station.y);
+        let site = self
+            .station
+            .pending_sites
+            .iter()
+            .position(|&site| find_path(self, site, station, true).is_some())
+            .and_then(|index| self.station.take_site(index));
+
+        if let Some(site) = site {
+            extractor.target_position = Some(site);
+            self.log(format_args!(
+                "Station assigned resource at ({}, {}) to Extractor.",
+                site.0, site.1
+            ));
+        }
+    }
+
+    // Ramasse la ressource de la case de l'extracteur, qui abandonne sa cible
+    pub(crate) fn harvest(&mut self, extractor: &mut RobotExtractor) {
+        let (x, y) = (extractor.x, extractor.y);
+        extractor.target_position = None;
+        if extractor.cargo.is_some() {
+            return;
+        }
+        let kind = if self.energy[y][x] {
+            Some(ResourceKind::Energy)
+        } else if self.minerals[y][x] {
+            Some(ResourceKind::Minerals)
+        } else {
+            None
+        };
+        if let Some(kind) = kind {
+            extractor.collect_resource(kind);
+            self.log(format_args!("Collected {:?} at ({}, {})", kind, x, y));
+            self.energy[y][x] = false;
+            self.minerals[y][x] = false;
+            self.damage.mark(x, y);
+            self.station.pending_sites.retain(|&site| site != (x, y));
+            self.deplete((x, y), kind);
+        }
+    }
+
+    // Dépose la cargaison à l'arrivée à la station
+    pub(crate) fn unload(&mut self, extractor: &mut RobotExtractor) {
+        if !extractor.is_at_station() {
+            return;
+        }
+        if let Some(kind) = extractor.cargo.take() {
+            self.station.deposit(kind);
+            self.collected += 1;
+            self.log(format_args!(
+                "Extractor returned to the station with resource. Remaining resources: {}",
+                self.count_consumables()
+            ));
+        }
+    }
+
+    // Recharge à la station ; l'explorateur ne fait rien d'autre pendant ce tick
+    pub(crate) fn recharge_explorer(&self, explorer: &mut RobotExplorer) -> bool {
+        let robots = &self.config.robots;
+        if explorer.is_at_station() && explorer.battery < robots.battery_capacity {
+            explorer.battery =
+                (explorer.battery + self.recharge_rate()).min(robots.battery_capacity);
+            return true;
+        }
+        false
+    }
+
+    // Signale à la station le site trouvé par un explorateur revenu à la base
+    pub(crate) fn report_explorer_site(&mut self, explorer: &mut RobotExplorer) {
+        if !explorer.is_at_station() {
+            return;
+        }
+        if let Some((resource_x, resource_y)) = explorer.resource_position {
+            if self.station.report_site((resource_x, resource_y)) {
+                self.log(format_args!(
+                    "Explorer returned to the station and reported resource at ({}, {}).",
+                    resource_x, resource_y
+                ));
+            }
+            explorer.reported_sites.insert((resource_x, resource_y));
+            explorer.founded_resource = false;
+            explorer.resource_position = None;
+        }
+    }
+
+    // `exhausted` : plus aucun gisement accessible au début du tick
+    fn update_explorer(&mut self, explorer: &mut RobotExplorer, exhausted: bool) {
+        if self.recharge_explorer(explorer) {
+            return;
+        }
+
+        if exhausted || explorer.battery < self.config.robots.low_battery {
+            if explorer.is_at_station() {
+                return;
+            }
+            self.move_explorer_to_station(explorer);
+        } else if self.nightfall_near(explorer) {
+            // Rentre avant la nuit et attend l'aube à la station
+            if explorer.is_at_station() {
+                self.report_explorer_site(explorer);
+            } else {
+                self.move_explorer_to_station(explorer);
+            }
+        } else if explorer.resource_position.is_some() {
+            if explorer.is_at_station() {
+                self.report_explorer_site(explorer);
+            } else {
+                self.move_explorer_to_station(explorer);
+            }
+        } else {
+            match self.config.strategy {
+                ExplorerStrategy::Random => explorer.move_random(self),
+                ExplorerStrategy::Frontier => explorer.move_to_frontier(self),
+            }
+        }
+    }
+
+    pub(crate) fn move_explorer_to_station(&self, explorer: &mut RobotExplorer) {
+        if let Some(path) = explorer.return_to_station(self) {
+            if let Some((next_x, next_y)) = self.step_along(
+                &path,
+                false,
+                &mut explorer.move_progress,
+                &mut explorer.battery,
+            ) {
+                explorer.x = next_x;
+                explorer.y = next_y;
+            }
+        }
+    }
+
+    // Simule la mission sans affichage jusqu'à une condition de fin
+    pub fn run_headless(&mut self) -> MissionSummary {
+        while !self.game_over {
+            self.update_robot();
+        }
+        self.summary()
     }
 
     pub fn count_consumables(&self) -> usize {
@@ -236,102 +873,190 @@ impl Map {
     }
 }
 
-impl event::EventHandler for Map {
-    fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        graphics::clear(ctx, graphics::WHITE);
-
-        let explorer = self.robot_explorer.as_ref().unwrap();
-        let extractor = self.robot_extractor.as_ref().unwrap();
-
-        let explorer_at_station =
-            explorer.x == explorer.station_x && explorer.y == explorer.station_y;
-        let extractor_at_station =
-            extractor.x == extractor.station_x && extractor.y == extractor.station_y;
-
-        let all_resources_collected = self.count_consumables() == 0;
-
-        for y in 0..MAP_SIZE {
-            for x in 0..MAP_SIZE {
-                let rect = graphics::Rect::new(
-                    x as f32 * CELL_SIZE as f32,
-                    y as f32 * CELL_SIZE as f32,
-                    CELL_SIZE as f32,
-                    CELL_SIZE as f32,
-                );
-                let cell_color = if all_resources_collected
-                    && explorer.station_x == x
-                    && explorer.station_y == y
-                {
-                    STATION_COLOR
-                } else if self.obstacles[y][x] {
-                    OBSTACLE_COLOR
-                } else if self.energy[y][x] {
-                    ENERGY_COLOR
-                } else if self.minerals[y][x] {
-                    MINERALS_COLOR
-                } else if self.science_interests[y][x] {
-                    SCIENCE_INTERESTS_COLOR
-                } else {
-                    DEFAULT_COLOR
-                };
-
-                let fog_color = if self.fog_of_war[y][x] {
-                    FOG_COLOR
-                } else {
-                    cell_color
-                };
-
-                let cell = graphics::Mesh::new_rectangle(
-                    ctx,
-                    graphics::DrawMode::fill(),
-                    rect,
-                    fog_color,
-                )?;
-                graphics::draw(ctx, &cell, graphics::DrawParam::default())?;
-            }
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::daylight::DayCycle;
+
+    // Carte plate sans obstacle ni ressource, station et flotte en (0, 0)
+    pub(crate) fn flat_map(config: MissionConfig) -> Map {
+        let mut map = Map::with_seed(1, &config);
+        map.verbose = false;
+        let battery = config.robots.battery_capacity;
+        map.station = Station::new(0, 0);
+        map.robot_explorers = (0..config.explorers)
+            .map(|_| RobotExplorer::new(0, 0, battery))
+            .collect();
+        map.robot_extractors = (0..config.extractors)
+            .map(|_| RobotExtractor::new(0, 0, battery))
+            .collect();
+        map.metrics = Metrics::new(config.explorers, config.extractors);
+        map.fog_of_war[0][0] = false;
+        map.refresh_reachability();
+        map.update_visibility();
+        map
+    }
+
+    pub(crate) fn place(map: &mut Map, (x, y): (usize, usize), kind: ResourceKind) {
+        match kind {
+            ResourceKind::Energy => map.energy[y][x] = true,
+            ResourceKind::Minerals => map.minerals[y][x] = true,
         }
+        map.resources.push((x, y));
+        map.refresh_reachability();
+    }
 
-        let explorer_x = explorer.x as f32 * CELL_SIZE as f32 + CELL_SIZE as f32 / 2.0;
-        let explorer_y = explorer.y as f32 * CELL_SIZE as f32 + CELL_SIZE as f32 / 2.0;
-        let explorer_circle = graphics::Mesh::new_circle(
-            ctx,
-            graphics::DrawMode::fill(),
-            [explorer_x, explorer_y],
-            CELL_SIZE as f32 / 2.0,
-            1.0,
-            ROBOT_EXPLORER_COLOR,
-        )?;
-        graphics::draw(ctx, &explorer_circle, graphics::DrawParam::default())?;
-
-        let extractor_x = extractor.x as f32 * CELL_SIZE as f32 + CELL_SIZE as f32 / 2.0;
-        let extractor_y = extractor.y as f32 * CELL_SIZE as f32 + CELL_SIZE as f32 / 2.0;
-        let extractor_circle = graphics::Mesh::new_circle(
-            ctx,
-            graphics::DrawMode::fill(),
-            [extractor_x, extractor_y],
-            CELL_SIZE as f32 / 2.0,
-            1.0,
-            ROBOT_EXTRACTOR_COLOR,
-        )?;
-        graphics::draw(ctx, &extractor_circle, graphics::DrawParam::default())?;
-
-        graphics::present(ctx)?;
-        Ok(())
-    }
-
-    fn update(&mut self, ctx: &mut Context) -> GameResult {
-        while timer::check_update_time(ctx, 1) {
-            if self.update_timer.as_secs() == 0 {
-                self.update_timer = Duration::from_secs(1);
-            } else {
-                self.update_timer = self
-                    .update_timer
-                    .checked_sub(Duration::from_secs(1))
-                    .unwrap_or(Duration::from_secs(0));
-            }
+    fn config(map_size: usize) -> MissionConfig {
+        MissionConfig {
+            map_size,
+            explorers: 1,
+            extractors: 1,
+            strategy: ExplorerStrategy::Frontier,
+            ..MissionConfig::default()
+        }
+    }
 
-            self.update_robot();
+    // Tous les robots attendent sur place à chaque tick
+    fn run_waiting(map: &mut Map) {
+        while !map.game_over {
+            map.orders = (0..map.robot_explorers.len())
+                .map(RobotRef::Explorer)
+                .chain((0..map.robot_extractors.len()).map(RobotRef::Extractor))
+                .map(|robot| (robot, Action::Wait))
+                .collect();
+            map.update_robot();
+        }
+    }
+
+    #[test]
+    fn collecting_every_resource_wins() {
+        let mut map = flat_map(config(6));
+        place(&mut map, (3, 2), ResourceKind::Energy);
+        place(&mut map, (1, 4), ResourceKind::Minerals);
+
+        let summary = map.run_headless();
+        assert_eq!(summary.outcome, Some(MissionOutcome::AllResourcesCollected));
+        assert_eq!((summary.collected, summary.remaining), (2, 0));
+        assert_eq!(summary.unreachable, 0);
+        assert_eq!((map.station.energy, map.station.minerals), (1, 1));
+        assert!(summary.explored_percent > 0.0);
+    }
+
+    #[test]
+    fn walled_off_resources_count_as_unreachable() {
+        let mut map = flat_map(config(6));
+        for (x, y) in [(4, 4), (4, 5), (5, 4)] {
+            map.obstacles[y][x] = true;
+        }
+        place(&mut map, (5, 5), ResourceKind::Energy);
+
+        let summary = map.run_headless();
+        assert_eq!(summary.outcome, Some(MissionOutcome::AllResourcesCollected));
+        assert_eq!(summary.ticks, 1);
+        assert_eq!((summary.collected, summary.remaining), (0, 1));
+        assert_eq!(summary.unreachable, 1);
+    }
+
+    #[test]
+    fn stockpile_goals_end_when_reached_or_out_of_reach() {
+        let stockpile = |energy| MissionConfig {
+            victory: Victory::Stockpile {
+                energy,
+                minerals: 0,
+            },
+            ..config(6)
+        };
+        let mut map = flat_map(stockpile(1));
+        place(&mut map, (3, 3), ResourceKind::Energy);
+        assert_eq!(
+            map.run_headless().outcome,
+            Some(MissionOutcome::StockpileReached)
+        );
+
+        let mut map = flat_map(stockpile(2));
+        place(&mut map, (3, 3), ResourceKind::Energy);
+        assert_eq!(
+            map.run_headless().outcome,
+            Some(MissionOutcome::ResourcesExhausted)
+        );
+        assert_eq!(map.station.energy, 1);
+    }
+
+    #[test]
+    fn idle_robots_end_the_mission_without_progress() {
+        let mut map = flat_map(MissionConfig {
+            stall_ticks: 20,
+            ..config(6)
+        });
+        place(&mut map, (3, 3), ResourceKind::Energy);
+        run_waiting(&mut map);
+        assert_eq!(map.outcome, Some(MissionOutcome::NoProgress));
+        // Le premier tick relève l'état de départ
+        assert_eq!(map.tick, 1 + 20);
+    }
+
+    #[test]
+    fn nights_longer_than_the_stall_limit_do_not_end_the_mission() {
+        let mut map = flat_map(MissionConfig {
+            stall_ticks: 100,
+            day: DayCycle {
+                length: 400,
+                night: 0.5,
+                ..DayCycle::default()
+            },
+            ..config(8)
+        });
+        place(&mut map, (7, 7), ResourceKind::Energy);
+        // La nuit tombe vers le tick 180 et dure jusque vers le tick 390
+        map.tick = 175;
+
+        let summary = map.run_headless();
+        assert_eq!(summary.outcome, Some(MissionOutcome::AllResourcesCollected));
+        assert!(summary.ticks > 390);
+        assert_eq!(summary.collected, 1);
+    }
+
+    #[test]
+    fn endless_colonies_ignore_stalls_until_the_budget() {
+        let mut map = flat_map(MissionConfig {
+            stall_ticks: 20,
+            max_ticks: 50,
+            victory: Victory::Endless,
+            ..config(6)
+        });
+        place(&mut map, (3, 3), ResourceKind::Energy);
+        run_waiting(&mut map);
+        assert_eq!(map.outcome, Some(MissionOutcome::ColonySustained));
+        assert_eq!(map.tick, 50);
+    }
+
+    #[test]
+    fn missions_stop_at_the_tick_budget() {
+        let mut map = flat_map(MissionConfig {
+            max_ticks: 5,
+            ..config(20)
+        });
+        place(&mut map, (19, 19), ResourceKind::Minerals);
+
+        let summary = map.run_headless();
+        assert_eq!(summary.outcome, Some(MissionOutcome::TickBudgetExhausted));
+        assert_eq!(summary.ticks, 5);
+        assert_eq!((summary.collected, summary.remaining), (0, 1));
+    }
+
+    #[test]
+    fn robots_with_empty_batteries_are_stranded() {
+        let mut map = flat_map(config(6));
+        place(&mut map, (5, 5), ResourceKind::Energy);
+        for explorer in map.robot_explorers.iter_mut() {
+            (explorer.x, explorer.y, explorer.battery) = (2, 2, 0.0);
+        }
+        for extractor in map.robot_extractors.iter_mut() {
+            (extractor.x, extractor.y, extractor.battery) = (3, 1, 0.0);
         }
-        Ok(())
+
+        let summary = map.run_headless();
+        assert_eq!(summary.outcome, Some(MissionOutcome::AllRobotsStranded));
+        assert_eq!(summary.ticks, 1);
     }
 }
@@ -0,0 +1,167 @@
+use serde::Serialize;
+use std::fmt;
+
+// Recherches de chemin lancées par un robot depuis le dernier relevé
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize)]
+pub struct SearchStats {
+    pub searches: usize,
+    pub failed: usize,
+    // Cases développées par A*, mesure du coût des recherches
+    pub expanded: usize,
+}
+
+impl SearchStats {
+    pub fn record(&mut self, found: bool, expanded: usize) {
+        self.searches += 1;
+        if !found {
+            self.failed += 1;
+        }
+        self.expanded += expanded;
+    }
+
+    fn add(&mut self, other: SearchStats) {
+        self.searches += other.searches;
+        self.failed += other.failed;
+        self.expanded += other.expanded;
+    }
+}
+
+// Compteurs d'un robot sur toute la mission
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RobotMetrics {
+    pub distance: usize,
+    // Immobile à la station, batterie pleine, sans rien à faire
+    pub idle_ticks: usize,
+    // Immobile ailleurs : recharge, effort de montée ou chemin introuvable
+    pub waiting_ticks: usize,
+    pub carrying_ticks: usize,
+    pub planning: SearchStats,
+}
+
+// État d'un robot relevé à chaque tick
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RobotSample {
+    pub position: (usize, usize),
+    pub at_station: bool,
+    pub full_battery: bool,
+    pub carrying: bool,
+}
+
+// Mesures de la mission, mises à jour à chaque tick
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Metrics {
+    pub explorers: Vec<RobotMetrics>,
+    pub extractors: Vec<RobotMetrics>,
+    // Pourcentage de la carte découverte à la fin de chaque tick
+    pub coverage: Vec<f32>,
+    pub first_discovery: Option<usize>,
+    pub first_delivery: Option<usize>,
+    pub breakdowns: usize,
+    pub repairs: usize,
+}
+
+impl Metrics {
+    pub fn new(explorers: usize, extractors: usize) -> Self {
+        Metrics {
+            explorers: vec![RobotMetrics::default(); explorers],
+            extractors: vec![RobotMetrics::default(); extractors],
+            ..Metrics::default()
+        }
+    }
+
+    pub fn robots(&self) -> impl Iterator<Item = &RobotMetrics> {
+        self.explorers.iter().chain(self.extractors.iter())
+    }
+
+    // Compare l'état des robots avant et après le tick, explorateurs puis extracteurs
+    pub fn record_tick(&mut self, before: &[RobotSample], after: &[RobotSample]) {
+        let robots = self.explorers.iter_mut().chain(self.extractors.iter_mut());
+        for ((robot, before), after) in robots.zip(before).zip(after) {
+            if before.position != after.position {
+                robot.distance += 1;
+            } else if after.at_station && after.full_battery {
+                robot.idle_ticks += 1;
+            } else {
+                robot.waiting_ticks += 1;
+            }
+            if after.carrying {
+                robot.carrying_ticks += 1;
+            }
+        }
+    }
+
+    pub fn record_searches(&mut self, explorers: &[SearchStats], extractors: &[SearchStats]) {
+        for (robot, stats) in self.explorers.iter_mut().zip(explorers) {
+            robot.planning.add(*stats);
+        }
+        for (robot, stats) in self.extractors.iter_mut().zip(extractors) {
+            robot.planning.add(*stats);
+        }
+    }
+
+    pub fn distance(&self) -> usize {
+        self.robots().map(|robot| robot.distance).sum()
+    }
+
+    pub fn idle_ticks(&self) -> usize {
+        self.robots().map(|robot| robot.idle_ticks).sum()
+    }
+
+    // Tick auquel la couverture atteint le pourcentage demandé
+    pub fn coverage_tick(&self, percent: f32) -> Option<usize> {
+        self.coverage
+            .iter()
+            .position(|&coverage| coverage >= percent)
+            .map(|index| index + 1)
+    }
+}
+
+fn tick_label(tick: Option<usize>) -> String {
+    tick.map_or_else(|| "never".to_string(), |tick| format!("tick {}", tick))
+}
+
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "First discovery: {}, first delivery: {}",
+            tick_label(self.first_discovery),
+            tick_label(self.first_delivery)
+        )?;
+        writeln!(
+            f,
+            "Coverage 50%: {}, 90%: {}, final {:.1}%",
+            tick_label(self.coverage_tick(50.0)),
+            tick_label(self.coverage_tick(90.0)),
+            self.coverage.last().copied().unwrap_or(0.0)
+        )?;
+        if self.breakdowns > 0 {
+            writeln!(
+                f,
+                "Breakdowns: {}, repairs: {}",
+                self.breakdowns, self.repairs
+            )?;
+        }
+        for (kind, robots) in [
+            ("Explorer", &self.explorers),
+            ("Extractor", &self.extractors),
+        ] {
+            for (index, robot) in robots.iter().enumerate() {
+                writeln!(
+                    f,
+                    "{} {}: {} cells, {} idle, {} waiting, {} carrying, {} searches ({} failed, {} cells expanded)",
+                    kind,
+                    index,
+                    robot.distance,
+                    robot.idle_ticks,
+                    robot.waiting_ticks,
+                    robot.carrying_ticks,
+                    robot.planning.searches,
+                    robot.planning.failed,
+                    robot.planning.expanded
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
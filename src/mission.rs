@@ -0,0 +1,282 @@
+use std::fmt;
+
+use crate::brain::Scripts;
+use crate::daylight::DayCycle;
+use crate::growth::Growth;
+use crate::hazards::Hazards;
+
+use crate::{BATTERY_CAPACITY, BATTERY_RECHARGE, LOW_BATTERY, MAX_CLIMB, MAX_LOADED_CLIMB};
+use crate::{MAP_SIZE, MAX_MISSION_TICKS, NB_EXPLORERS, NB_EXTRACTORS, SENSOR_RANGE, STALL_TICKS};
+
+const MAX_MAP_SIZE: usize = 512;
+
+// Caractéristiques communes à tous les robots de la mission
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capabilities {
+    pub battery_capacity: f32,
+    pub battery_recharge: f32,
+    pub low_battery: f32,
+    pub max_climb: f64,
+    pub max_loaded_climb: f64,
+    pub sensor_range: usize,
+    // Probabilité par tick qu'un robot hors de la station tombe en panne
+    pub breakdown_rate: f64,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities {
+            battery_capacity: BATTERY_CAPACITY,
+            battery_recharge: BATTERY_RECHARGE,
+            low_battery: LOW_BATTERY,
+            max_climb: MAX_CLIMB,
+            max_loaded_climb: MAX_LOADED_CLIMB,
+            sensor_range: SENSOR_RANGE,
+            breakdown_rate: 0.0,
+        }
+    }
+}
+
+// Déplacement des explorateurs lorsqu'aucune case voisine n'est inexplorée
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ExplorerStrategy {
+    // Case voisine praticable tirée au hasard
+    Random,
+    // Chemin vers la case inexplorée accessible la plus proche
+    Frontier,
+}
+
+impl ExplorerStrategy {
+    pub const ALL: [ExplorerStrategy; 2] = [ExplorerStrategy::Random, ExplorerStrategy::Frontier];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExplorerStrategy::Random => "random",
+            ExplorerStrategy::Frontier => "frontier",
+        }
+    }
+
+    pub fn parse(label: &str) -> Result<ExplorerStrategy, String> {
+        ExplorerStrategy::ALL
+            .into_iter()
+            .find(|strategy| strategy.label() == label)
+            .ok_or_else(|| {
+                format!(
+                    "unknown explorer strategy '{}', expected 'random' or 'frontier'",
+                    label
+                )
+            })
+    }
+}
+
+// Règle de placement de la station
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StationPlacement {
+    // Case libre tirée au hasard dans la plus grande région praticable
+    Random,
+    // Case libre de la plus grande région la plus proche du centre
+    Center,
+    // Case imposée, dégagée si nécessaire
+    Fixed(usize, usize),
+}
+
+// Condition de victoire de la mission
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Victory {
+    // Toutes les ressources atteignables ramenées à la station
+    AllResources,
+    // Stocks minimaux à atteindre à la station
+    Stockpile { energy: usize, minerals: usize },
+    // Colonie sans fin : la mission dure tout son budget de ticks
+    Endless,
+}
+
+// Paramètres d'une mission fixés au lancement
+#[derive(Clone, Debug, PartialEq)]
+pub struct MissionConfig {
+    pub map_size: usize,
+    pub explorers: usize,
+    pub extractors: usize,
+    pub max_ticks: usize,
+    pub stall_ticks: usize,
+    pub strategy: ExplorerStrategy,
+    pub robots: Capabilities,
+    pub station: StationPlacement,
+    pub victory: Victory,
+    // Cerveaux Rhai remplaçant le comportement intégré des robots
+    pub scripts: Scripts,
+    // Tempêtes, éboulements et zones dangereuses
+    pub hazards: Hazards,
+    pub day: DayCycle,
+    // Repousse et apparition de gisements
+    pub growth: Growth,
+}
+
+impl Default for MissionConfig {
+    fn default() -> Self {
+        MissionConfig {
+            map_size: MAP_SIZE,
+            explorers: NB_EXPLORERS,
+            extractors: NB_EXTRACTORS,
+            max_ticks: MAX_MISSION_TICKS,
+            stall_ticks: STALL_TICKS,
+            strategy: ExplorerStrategy::Random,
+            robots: Capabilities::default(),
+            station: StationPlacement::Random,
+            victory: Victory::AllResources,
+            scripts: Scripts::default(),
+            hazards: Hazards::default(),
+            day: DayCycle::default(),
+            growth: Growth::default(),
+        }
+    }
+}
+
+impl MissionConfig {
+    // Liste toutes les incohérences plutôt que de s'arrêter à la première
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errors = vec![];
+        if !(2..=MAX_MAP_SIZE).contains(&self.map_size) {
+            errors.push(format!(
+                "map size must be between 2 and {}, got {}",
+                MAX_MAP_SIZE, self.map_size
+            ));
+        }
+        if self.explorers == 0 {
+            errors.push("at least one explorer is required".to_string());
+        }
+        if self.extractors == 0 {
+            errors.push("at least one extractor is required".to_string());
+        }
+        if self.max_ticks == 0 {
+            errors.push("max_ticks must be positive".to_string());
+        }
+        if self.stall_ticks == 0 {
+            errors.push("stall_ticks must be positive".to_string());
+        }
+
+        let robots = &self.robots;
+        if robots.battery_capacity <= 0.0 {
+            errors.push("battery_capacity must be positive".to_string());
+        }
+        if robots.battery_recharge <= 0.0 {
+            errors.push("battery_recharge must be positive".to_string());
+        }
+        if robots.low_battery < 0.0 || robots.low_battery >= robots.battery_capacity {
+            errors.push(format!(
+                "low_battery must be between 0 and battery_capacity ({}), got {}",
+                robots.battery_capacity, robots.low_battery
+            ));
+        }
+        if robots.max_climb < 0.0 || robots.max_loaded_climb < 0.0 {
+            errors.push("climb limits must not be negative".to_string());
+        }
+        if robots.sensor_range >= self.map_size {
+            errors.push(format!(
+                "sensor_range must be smaller than the map size ({}), got {}",
+                self.map_size, robots.sensor_range
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&robots.breakdown_rate) {
+            errors.push(format!(
+                "breakdown_rate must be between 0 and 1, got {}",
+                robots.breakdown_rate
+            ));
+        }
+
+        if let StationPlacement::Fixed(x, y) = self.station {
+            if x >= self.map_size || y >= self.map_size {
+                errors.push(format!(
+                    "station position ({}, {}) is outside the {}x{} map",
+                    x, y, self.map_size, self.map_size
+                ));
+            }
+        }
+        if self.victory
+            == (Victory::Stockpile {
+                energy: 0,
+                minerals: 0,
+            })
+        {
+            errors.push("a stockpile victory needs an energy or minerals target".to_string());
+        }
+        errors.extend(self.hazards.validate(self.map_size));
+        errors.extend(self.day.validate(self.robots.sensor_range));
+        errors.extend(self.growth.validate());
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+}
+
+// Raison de fin de mission
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MissionOutcome {
+    // Toutes les ressources atteignables ont été ramenées à la station
+    AllResourcesCollected,
+    // Les stocks demandés sont atteints à la station
+    StockpileReached,
+    // Plus aucune ressource atteignable alors que les stocks demandés manquent
+    ResourcesExhausted,
+    // Le budget de ticks est épuisé
+    TickBudgetExhausted,
+    // Plus aucun robot ne peut se déplacer
+    AllRobotsStranded,
+    // Aucune progression pendant `stall_ticks` ticks
+    NoProgress,
+    // Une colonie sans fin a tenu tout son budget de ticks
+    ColonySustained,
+}
+
+impl MissionOutcome {
+    pub fn is_success(self) -> bool {
+        matches!(
+            self,
+            MissionOutcome::AllResourcesCollected
+                | MissionOutcome::StockpileReached
+                | MissionOutcome::ColonySustained
+        )
+    }
+}
+
+// Statistiques de fin de mission
+#[derive(Clone, Debug)]
+pub struct MissionSummary {
+    pub outcome: Option<MissionOutcome>,
+    pub ticks: usize,
+    pub collected: usize,
+    pub remaining: usize,
+    pub unreachable: usize,
+    pub explored_percent: f32,
+    // Cases parcourues par l'ensemble des robots
+    pub distance: usize,
+    // Ticks passés par les robots sans rien à faire à la station
+    pub idle_ticks: usize,
+    // Ressources ramenées pour 1000 ticks
+    pub throughput: f32,
+}
+
+impl fmt::Display for MissionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.outcome {
+            Some(outcome) => write!(f, "{:?}", outcome)?,
+            None => write!(f, "InProgress")?,
+        }
+        write!(
+            f,
+            " after {} ticks: {} collected, {} remaining ({} unreachable), {:.1}% explored, {} cells travelled, {} idle ticks, {:.1} resources per 1000 ticks",
+            self.ticks,
+            self.collected,
+            self.remaining,
+            self.unreachable,
+            self.explored_percent,
+            self.distance,
+            self.idle_ticks,
+            self.throughput
+        )
+    }
+}
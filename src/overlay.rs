@@ -0,0 +1,152 @@
+use ggez::graphics::{self, Color, DrawMode, DrawParam, MeshBuilder, Rect, Text};
+use ggez::{Context, GameResult};
+
+use crate::map::Map;
+use crate::robot::SearchTrace;
+use crate::CELL_SIZE;
+
+// Une couleur par robot, explorateurs puis extracteurs
+const PALETTE: [Color; 8] = [
+    Color::new(0.9, 0.1, 0.1, 1.0),
+    Color::new(0.1, 0.6, 0.9, 1.0),
+    Color::new(0.2, 0.8, 0.2, 1.0),
+    Color::new(0.9, 0.5, 0.0, 1.0),
+    Color::new(0.7, 0.2, 0.9, 1.0),
+    Color::new(0.9, 0.9, 0.1, 1.0),
+    Color::new(0.0, 0.8, 0.7, 1.0),
+    Color::new(0.9, 0.3, 0.6, 1.0),
+];
+const CLOSED_ALPHA: f32 = 0.2;
+const OPEN_ALPHA: f32 = 0.7;
+
+// Ce que le calque montre pour un robot
+struct RobotPlan<'a> {
+    label: String,
+    search: Option<&'a SearchTrace>,
+    target: Option<(usize, usize)>,
+    frontier: &'a [(usize, usize)],
+}
+
+fn robot_plans(map: &Map) -> Vec<RobotPlan<'_>> {
+    let explorers = map
+        .robot_explorers
+        .iter()
+        .enumerate()
+        .map(|(index, e)| RobotPlan {
+            label: format!("Explorer {}", index),
+            search: e.last_search.as_ref(),
+            target: e.resource_position,
+            frontier: &e.frontier,
+        });
+    let extractors = map
+        .robot_extractors
+        .iter()
+        .enumerate()
+        .map(|(index, e)| RobotPlan {
+            label: format!("Extractor {}", index),
+            search: e.last_search.as_ref(),
+            target: e.target_position,
+            frontier: &[],
+        });
+    explorers.chain(extractors).collect()
+}
+
+fn with_alpha(color: Color, a: f32) -> Color {
+    Color { a, ..color }
+}
+
+fn cell_rect((x, y): (usize, usize), inset: f32) -> Rect {
+    Rect::new(
+        x as f32 * CELL_SIZE + inset,
+        y as f32 * CELL_SIZE + inset,
+        CELL_SIZE - 2.0 * inset,
+        CELL_SIZE - 2.0 * inset,
+    )
+}
+
+fn cell_center((x, y): (usize, usize)) -> [f32; 2] {
+    [(x as f32 + 0.5) * CELL_SIZE, (y as f32 + 0.5) * CELL_SIZE]
+}
+
+// Calque de débogage, dans le repère du monde : ensembles fermé (plein) et ouvert
+// (contour) de la dernière recherche A*, chemin prévu, but de la recherche (croix),
+// cible du robot (anneau) et frontière des explorateurs (points)
+pub fn draw_path_overlay(ctx: &mut Context, map: &Map) -> GameResult {
+    let mut mesh = MeshBuilder::new();
+    let mut empty = true;
+
+    for (plan, color) in robot_plans(map).iter().zip(PALETTE.iter().cycle()) {
+        if let Some(search) = plan.search {
+            for &cell in &search.closed {
+                mesh.rectangle(
+                    DrawMode::fill(),
+                    cell_rect(cell, 0.0),
+                    with_alpha(*color, CLOSED_ALPHA),
+                );
+            }
+            for &cell in &search.open {
+                mesh.rectangle(
+                    DrawMode::stroke(1.0),
+                    cell_rect(cell, 3.0),
+                    with_alpha(*color, OPEN_ALPHA),
+                );
+            }
+            if search.path.len() >= 2 {
+                let points: Vec<[f32; 2]> = search.path.iter().map(|&c| cell_center(c)).collect();
+                mesh.line(&points, 3.0, *color)?;
+            }
+            let rect = cell_rect(search.goal, 6.0);
+            mesh.line(
+                &[[rect.x, rect.y], [rect.right(), rect.bottom()]],
+                2.0,
+                *color,
+            )?;
+            mesh.line(
+                &[[rect.right(), rect.y], [rect.x, rect.bottom()]],
+                2.0,
+                *color,
+            )?;
+            empty = false;
+        }
+        if let Some(target) = plan.target {
+            mesh.circle(
+                DrawMode::stroke(2.0),
+                cell_center(target),
+                CELL_SIZE / 2.0 - 2.0,
+                0.5,
+                *color,
+            );
+            empty = false;
+        }
+        for &cell in plan.frontier {
+            mesh.circle(DrawMode::fill(), cell_center(cell), 3.0, 0.5, *color);
+            empty = false;
+        }
+    }
+
+    // Un MeshBuilder vide ne peut pas être construit
+    if empty {
+        return Ok(());
+    }
+    let mesh = mesh.build(ctx)?;
+    graphics::draw(ctx, &mesh, DrawParam::default())
+}
+
+// Correspondance entre couleurs et robots, en bas à droite de l'écran
+pub fn draw_overlay_legend(ctx: &mut Context, map: &Map) -> GameResult {
+    let screen = graphics::screen_coordinates(ctx);
+    let plans = robot_plans(map);
+    for (row, (plan, color)) in plans.iter().zip(PALETTE.iter().cycle()).enumerate() {
+        let text = Text::new(plan.label.as_str());
+        let (w, h) = text.dimensions(ctx);
+        let y = screen.h - (plans.len() - row) as f32 * (h as f32 + 2.0) - 4.0;
+        graphics::draw(
+            ctx,
+            &text,
+            DrawParam::default()
+                .dest([screen.w - w as f32 - 4.0, y])
+                .color(*color),
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,510 @@
+use ggez::graphics::spritebatch::SpriteBatch;
+use ggez::graphics::{self, Color, DrawParam, FilterMode, Image, MeshBuilder, Rect};
+use ggez::{timer, Context, GameResult};
+use std::collections::BTreeMap;
+
+use crate::hazards::EventKind;
+use crate::map::{Damage, Map, Visibility};
+use crate::terrain::Biome;
+use crate::CELL_SIZE;
+use crate::{
+    DEFAULT_COLOR, ENERGY_COLOR, FOG_COLOR, MINERALS_COLOR, OBSTACLE_COLOR, ROBOT_EXPLORER_COLOR,
+    ROBOT_EXTRACTOR_COLOR, SCIENCE_INTERESTS_COLOR, STATION_COLOR,
+};
+
+// Côté d'un bloc de cases reconstruit d'un seul tenant
+const CHUNK_SIZE: usize = 16;
+// Assombrissement des cases déjà vues mais hors de portée des capteurs
+const REMEMBERED_DIM: f32 = 0.5;
+// Voiles des tempêtes de poussière et des zones dangereuses
+const STORM_COLOR: Color = Color::new(0.8, 0.6, 0.3, 0.45);
+const HAZARD_COLOR: Color = Color::new(0.9, 0.1, 0.1, 0.3);
+// Opacité du voile au cœur de la nuit
+const NIGHT_DARKNESS: f32 = 0.6;
+
+// Sprites disponibles, dans l'ordre de dessin (terrain puis objets)
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum SpriteKind {
+    Plains,
+    Crater,
+    Ridge,
+    Ice,
+    Obstacle,
+    Energy,
+    Minerals,
+    Robot,
+}
+
+impl SpriteKind {
+    fn path(self) -> &'static str {
+        match self {
+            SpriteKind::Plains => "/256_Grass 02 Blades.png",
+            SpriteKind::Crater => "/grey.png",
+            SpriteKind::Ridge => "/grey.png",
+            SpriteKind::Ice => "/tile_grey.png",
+            SpriteKind::Obstacle => "/rock.png",
+            SpriteKind::Energy => "/yellow.png",
+            SpriteKind::Minerals => "/blue.png",
+            SpriteKind::Robot => "/robot.png",
+        }
+    }
+
+    fn terrain(biome: Biome) -> SpriteKind {
+        match biome {
+            Biome::Plains => SpriteKind::Plains,
+            Biome::Crater => SpriteKind::Crater,
+            Biome::Ridge => SpriteKind::Ridge,
+            Biome::Ice => SpriteKind::Ice,
+        }
+    }
+}
+
+// Sprites chargés depuis les dossiers de ressources ; None si l'image est absente
+pub struct Tileset {
+    sprites: BTreeMap<SpriteKind, Option<Image>>,
+}
+
+impl Tileset {
+    pub fn load(ctx: &mut Context) -> Self {
+        let kinds = [
+            SpriteKind::Plains,
+            SpriteKind::Crater,
+            SpriteKind::Ridge,
+            SpriteKind::Ice,
+            SpriteKind::Obstacle,
+            SpriteKind::Energy,
+            SpriteKind::Minerals,
+            SpriteKind::Robot,
+        ];
+        Tileset {
+            sprites: kinds
+                .iter()
+                .map(|&kind| (kind, load_sprite(ctx, kind.path())))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, kind: SpriteKind) -> Option<&Image> {
+        self.sprites.get(&kind).and_then(|sprite| sprite.as_ref())
+    }
+}
+
+fn load_sprite(ctx: &mut Context, path: &str) -> Option<Image> {
+    match Image::new(ctx, path) {
+        Ok(mut image) => {
+            image.set_filter(FilterMode::Nearest);
+            Some(image)
+        }
+        Err(error) => {
+            println!("Sprite {} unavailable, using flat colours: {}", path, error);
+            None
+        }
+    }
+}
+
+fn shade(color: Color, factor: f32) -> Color {
+    Color::new(
+        color.r * factor,
+        color.g * factor,
+        color.b * factor,
+        color.a,
+    )
+}
+
+// Paramètres pour étirer une image sur une case
+fn cell_param(image: &Image, x: f32, y: f32, tint: Color) -> DrawParam {
+    DrawParam::default()
+        .dest([x, y])
+        .scale([
+            CELL_SIZE / image.width() as f32,
+            CELL_SIZE / image.height() as f32,
+        ])
+        .color(tint)
+}
+
+// Ce qui détermine l'apparence d'une case
+#[derive(Copy, Clone, PartialEq)]
+struct CellKey {
+    visibility: Visibility,
+    station: bool,
+    obstacle: bool,
+    energy: bool,
+    minerals: bool,
+    science: bool,
+    biome: Biome,
+}
+
+// En vue divine, toute la carte est visible avec son contenu réel ; sinon les cases
+// hors de portée affichent les ressources mémorisées
+fn cell_key(map: &Map, all_collected: bool, god_view: bool, x: usize, y: usize) -> CellKey {
+    let visibility = if god_view {
+        Visibility::Visible
+    } else {
+        map.visibility(x, y)
+    };
+    let (energy, minerals) = match visibility {
+        Visibility::Visible => (map.energy[y][x], map.minerals[y][x]),
+        _ => (map.seen_energy[y][x], map.seen_minerals[y][x]),
+    };
+    CellKey {
+        visibility,
+        station: all_collected && (map.station.x, map.station.y) == (x, y),
+        obstacle: map.obstacles[y][x],
+        energy,
+        minerals,
+        science: map.science_interests[y][x],
+        biome: map.biomes[y][x],
+    }
+}
+
+// Bloc de cases mis en cache : rectangles de couleur et lots de sprites
+struct Chunk {
+    x0: usize,
+    y0: usize,
+    x1: usize,
+    y1: usize,
+    // Une case du bloc a changé depuis sa dernière construction
+    dirty: bool,
+    mesh: Option<graphics::Mesh>,
+    batches: BTreeMap<SpriteKind, SpriteBatch>,
+}
+
+impl Chunk {
+    fn cells(&self) -> impl Iterator<Item = (usize, usize)> {
+        let (x0, x1) = (self.x0, self.x1);
+        (self.y0..self.y1).flat_map(move |y| (x0..x1).map(move |x| (x, y)))
+    }
+
+    fn rebuild(
+        &mut self,
+        ctx: &mut Context,
+        map: &Map,
+        tileset: &Tileset,
+        god_view: bool,
+    ) -> GameResult {
+        let mut mesh = MeshBuilder::new();
+        let mut rectangles = 0;
+        let mut batches: BTreeMap<SpriteKind, SpriteBatch> = BTreeMap::new();
+        let mut add =
+            |kind: Option<SpriteKind>, x: usize, y: usize, fallback: Color, tint: Color| {
+                let (px, py) = (x as f32 * CELL_SIZE, y as f32 * CELL_SIZE);
+                match kind.and_then(|kind| tileset.get(kind).map(|image| (kind, image))) {
+                    Some((kind, image)) => {
+                        batches
+                            .entry(kind)
+                            .or_insert_with(|| SpriteBatch::new(image.clone()))
+                            .add(cell_param(image, px, py, tint));
+                    }
+                    None => {
+                        let rect = Rect::new(px, py, CELL_SIZE, CELL_SIZE);
+                        mesh.rectangle(graphics::DrawMode::fill(), rect, fallback);
+                        rectangles += 1;
+                    }
+                }
+            };
+
+        let all_collected = map.count_consumables() == 0;
+        for (x, y) in self.cells() {
+            let key = cell_key(map, all_collected, god_view, x, y);
+
+            let shade_factor = match key.visibility {
+                Visibility::Remembered => map.hillshade(x, y) * REMEMBERED_DIM,
+                _ => map.hillshade(x, y),
+            };
+            let tint = shade(graphics::WHITE, shade_factor);
+            let terrain = SpriteKind::terrain(key.biome);
+
+            if key.visibility == Visibility::Unknown {
+                add(None, x, y, FOG_COLOR, FOG_COLOR);
+            } else if key.station {
+                add(None, x, y, STATION_COLOR, STATION_COLOR);
+            } else if key.science && !key.obstacle && !key.energy && !key.minerals {
+                add(
+                    None,
+                    x,
+                    y,
+                    shade(SCIENCE_INTERESTS_COLOR, shade_factor),
+                    tint,
+                );
+            } else {
+                let overlay = if key.obstacle {
+                    Some((SpriteKind::Obstacle, OBSTACLE_COLOR))
+                } else if key.energy {
+                    Some((SpriteKind::Energy, ENERGY_COLOR))
+                } else if key.minerals {
+                    Some((SpriteKind::Minerals, MINERALS_COLOR))
+                } else {
+                    None
+                };
+
+                match overlay {
+                    // Sprite d'obstacle ou de ressource dessiné par-dessus le terrain
+                    Some((kind, color)) if tileset.get(kind).is_some() => {
+                        add(
+                            Some(terrain),
+                            x,
+                            y,
+                            shade(DEFAULT_COLOR, shade_factor),
+                            tint,
+                        );
+                        add(Some(kind), x, y, color, tint);
+                    }
+                    // Sans sprite, la couleur d'origine remplace le terrain
+                    Some((_, color)) => add(None, x, y, shade(color, shade_factor), tint),
+                    None => add(
+                        Some(terrain),
+                        x,
+                        y,
+                        shade(DEFAULT_COLOR, shade_factor),
+                        tint,
+                    ),
+                }
+            }
+        }
+
+        self.mesh = if rectangles > 0 {
+            Some(mesh.build(ctx)?)
+        } else {
+            None
+        };
+        self.batches = batches;
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn draw(&self, ctx: &mut Context) -> GameResult {
+        if let Some(mesh) = &self.mesh {
+            graphics::draw(ctx, mesh, DrawParam::default())?;
+        }
+        for batch in self.batches.values() {
+            graphics::draw(ctx, batch, DrawParam::default())?;
+        }
+        Ok(())
+    }
+}
+
+// Couche de la carte : seuls les blocs dont une case a changé sont reconstruits
+pub struct MapLayer {
+    chunks: Vec<Chunk>,
+    // Nombre de blocs par ligne
+    columns: usize,
+    pub rebuilt_last_frame: usize,
+    // Désactive le brouillard pour le débogage
+    pub god_view: bool,
+    // État de l'affichage lors de la dernière construction des blocs
+    built_god_view: bool,
+    built_all_collected: bool,
+}
+
+impl MapLayer {
+    pub fn new(map_size: usize) -> Self {
+        let mut chunks = vec![];
+        for y0 in (0..map_size).step_by(CHUNK_SIZE) {
+            for x0 in (0..map_size).step_by(CHUNK_SIZE) {
+                chunks.push(Chunk {
+                    x0,
+                    y0,
+                    x1: (x0 + CHUNK_SIZE).min(map_size),
+                    y1: (y0 + CHUNK_SIZE).min(map_size),
+                    dirty: true,
+                    mesh: None,
+                    batches: BTreeMap::new(),
+                });
+            }
+        }
+        MapLayer {
+            chunks,
+            columns: map_size.div_ceil(CHUNK_SIZE),
+            rebuilt_last_frame: 0,
+            god_view: false,
+            built_god_view: false,
+            built_all_collected: false,
+        }
+    }
+
+    // Marque les blocs des cases modifiées depuis l'image précédente
+    pub fn invalidate(&mut self, damage: &Damage) {
+        if damage.all {
+            for chunk in self.chunks.iter_mut() {
+                chunk.dirty = true;
+            }
+            return;
+        }
+        for &(x, y) in &damage.cells {
+            let index = (y / CHUNK_SIZE) * self.columns + x / CHUNK_SIZE;
+            if let Some(chunk) = self.chunks.get_mut(index) {
+                chunk.dirty = true;
+            }
+        }
+    }
+
+    // Dessine les blocs visibles ; les blocs hors écran restent à reconstruire
+    pub fn draw(
+        &mut self,
+        ctx: &mut Context,
+        map: &Map,
+        tileset: &Tileset,
+        visible: Rect,
+    ) -> GameResult {
+        if self.god_view != self.built_god_view {
+            self.built_god_view = self.god_view;
+            self.invalidate(&Damage::everything());
+        }
+        // La station n'est dessinée qu'une fois toutes les ressources récoltées
+        let all_collected = map.count_consumables() == 0;
+        if all_collected != self.built_all_collected {
+            self.built_all_collected = all_collected;
+            self.invalidate(&Damage {
+                all: false,
+                cells: vec![(map.station.x, map.station.y)],
+            });
+        }
+        self.rebuilt_last_frame = 0;
+        for chunk in self.chunks.iter_mut() {
+            let bounds = Rect::new(
+                chunk.x0 as f32 * CELL_SIZE,
+                chunk.y0 as f32 * CELL_SIZE,
+                CHUNK_SIZE as f32 * CELL_SIZE,
+                CHUNK_SIZE as f32 * CELL_SIZE,
+            );
+            if !bounds.overlaps(&visible) {
+                continue;
+            }
+            if chunk.dirty {
+                chunk.rebuild(ctx, map, tileset, self.god_view)?;
+                self.rebuilt_last_frame += 1;
+            }
+            chunk.draw(ctx)?;
+        }
+        Ok(())
+    }
+}
+
+// Les robots bougent à chaque tick : ils sont redessinés dans leur propre lot
+// `positions` contient les positions interpolées, explorateurs puis extracteurs
+pub fn draw_robots(
+    ctx: &mut Context,
+    map: &Map,
+    tileset: &Tileset,
+    positions: &[(f32, f32)],
+) -> GameResult {
+    let colors = std::iter::repeat_n(ROBOT_EXPLORER_COLOR, map.robot_explorers.len()).chain(
+        std::iter::repeat_n(ROBOT_EXTRACTOR_COLOR, map.robot_extractors.len()),
+    );
+    let robots: Vec<(f32, f32, Color)> = positions
+        .iter()
+        .zip(colors)
+        .map(|(&(x, y), color)| (x, y, color))
+        .collect();
+
+    match tileset.get(SpriteKind::Robot) {
+        Some(image) => {
+            let mut batch = SpriteBatch::new(image.clone());
+            for &(x, y, color) in &robots {
+                batch.add(cell_param(image, x * CELL_SIZE, y * CELL_SIZE, color));
+            }
+            graphics::draw(ctx, &batch, DrawParam::default())
+        }
+        None if !robots.is_empty() => {
+            let mut mesh = MeshBuilder::new();
+            for &(x, y, color) in &robots {
+                mesh.circle(
+                    graphics::DrawMode::fill(),
+                    [
+                        x * CELL_SIZE + CELL_SIZE / 2.0,
+                        y * CELL_SIZE + CELL_SIZE / 2.0,
+                    ],
+                    CELL_SIZE / 2.0,
+                    1.0,
+                    color,
+                );
+            }
+            let mesh = mesh.build(ctx)?;
+            graphics::draw(ctx, &mesh, DrawParam::default())
+        }
+        None => Ok(()),
+    }
+}
+
+// Obscurité nocturne sur toute la carte ; `time` inclut la fraction du tick en cours
+pub fn draw_night(ctx: &mut Context, map: &Map, time: f32) -> GameResult {
+    let darkness = (1.0 - map.config.day.daylight(time)) * NIGHT_DARKNESS;
+    if darkness <= 0.0 {
+        return Ok(());
+    }
+    let side = map.size as f32 * CELL_SIZE;
+    let veil = graphics::Mesh::new_rectangle(
+        ctx,
+        graphics::DrawMode::fill(),
+        Rect::new(0.0, 0.0, side, side),
+        Color::new(0.0, 0.0, 0.08, darkness),
+    )?;
+    graphics::draw(ctx, &veil, DrawParam::default())
+}
+
+// Voile translucide sur les zones des événements en cours, dans le repère du monde
+pub fn draw_events(ctx: &mut Context, map: &Map) -> GameResult {
+    if map.events.is_empty() {
+        return Ok(());
+    }
+    let mut builder = MeshBuilder::new();
+    for active in &map.events {
+        let event = &active.event;
+        let color = match event.kind {
+            EventKind::DustStorm { .. } => STORM_COLOR,
+            _ => HAZARD_COLOR,
+        };
+        let x0 = event.x.saturating_sub(event.radius);
+        let y0 = event.y.saturating_sub(event.radius);
+        let x1 = (event.x + event.radius + 1).min(map.size);
+        let y1 = (event.y + event.radius + 1).min(map.size);
+        builder.rectangle(
+            graphics::DrawMode::fill(),
+            Rect::new(
+                x0 as f32 * CELL_SIZE,
+                y0 as f32 * CELL_SIZE,
+                (x1 - x0) as f32 * CELL_SIZE,
+                (y1 - y0) as f32 * CELL_SIZE,
+            ),
+            color,
+        );
+    }
+    let mesh = builder.build(ctx)?;
+    graphics::draw(ctx, &mesh, DrawParam::default())
+}
+
+// Encadre une case de la carte, dans le repère du monde
+pub fn draw_cell_marker(ctx: &mut Context, (x, y): (usize, usize), color: Color) -> GameResult {
+    let marker = graphics::Mesh::new_rectangle(
+        ctx,
+        graphics::DrawMode::stroke(2.0),
+        Rect::new(
+            x as f32 * CELL_SIZE,
+            y as f32 * CELL_SIZE,
+            CELL_SIZE,
+            CELL_SIZE,
+        ),
+        color,
+    )?;
+    graphics::draw(ctx, &marker, DrawParam::default())
+}
+
+// Affiche la durée moyenne d'une image pour mesurer le coût du rendu
+pub fn draw_frame_time(ctx: &mut Context, rebuilt_chunks: usize) -> GameResult {
+    let text = graphics::Text::new(format!(
+        "{:.2} ms/frame ({:.0} fps), {} chunks rebuilt",
+        timer::average_delta(ctx).as_secs_f64() * 1000.0,
+        timer::fps(ctx),
+        rebuilt_chunks
+    ));
+    // En bas à gauche, pour ne pas masquer le HUD
+    let screen = graphics::screen_coordinates(ctx);
+    let height = text.height(ctx) as f32;
+    graphics::draw(
+        ctx,
+        &text,
+        DrawParam::default()
+            .dest([4.0, screen.h - height - 4.0])
+            .color(ROBOT_EXPLORER_COLOR),
+    )
+}
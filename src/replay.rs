@@ -0,0 +1,946 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::brain::{self, Brain};
+use crate::camera::RobotRef;
+use crate::editor::Brush;
+use crate::hazards::{Event, Hazards, ScheduledEvent};
+use crate::map::{Damage, DispatchMode, Map};
+use crate::mission::{Capabilities, ExplorerStrategy, MissionConfig, StationPlacement, Victory};
+use crate::station::ResourceKind;
+use crate::terrain::GenerationSettings;
+
+const REPLAY_HEADER: &str = "cosmobots-replay 2";
+// Un état complet est conservé tous les KEYFRAME_INTERVAL ticks pour revenir en arrière
+const KEYFRAME_INTERVAL: usize = 100;
+
+// Action d'un robot déduite de l'état avant et après un tick
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Action {
+    Move(RobotRef, (usize, usize)),
+    Collect(RobotRef, ResourceKind),
+    Deposit(RobotRef, ResourceKind),
+    Report(RobotRef, (usize, usize)),
+    Assign(RobotRef, (usize, usize)),
+}
+
+// Modification de la carte faite dans le mode bac à sable, appliquée avant un tick
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Edit {
+    pub x: usize,
+    pub y: usize,
+    pub brush: Brush,
+}
+
+#[derive(Clone, Debug)]
+pub struct TickRecord {
+    pub tick: usize,
+    pub hash: u64,
+    pub edits: Vec<Edit>,
+    // Ordres d'un agent externe appliqués à ce tick
+    pub orders: Vec<(RobotRef, brain::Action)>,
+    pub actions: Vec<Action>,
+}
+
+// Une mission se rejoue à partir de sa graine, de ses paramètres et des modifications manuelles ;
+// les actions et empreintes de chaque tick servent à vérifier le déterminisme
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub seed: u64,
+    pub settings: Vec<(String, String)>,
+    pub config: MissionConfig,
+    pub dispatch_mode: DispatchMode,
+    pub ticks: Vec<TickRecord>,
+}
+
+// État des robots utile pour déduire leurs actions
+#[derive(Clone)]
+struct RobotSnapshot {
+    robot: RobotRef,
+    position: (usize, usize),
+    cargo: Option<ResourceKind>,
+    target: Option<(usize, usize)>,
+}
+
+fn snapshot(map: &Map) -> Vec<RobotSnapshot> {
+    let explorers = map
+        .robot_explorers
+        .iter()
+        .enumerate()
+        .map(|(index, e)| RobotSnapshot {
+            robot: RobotRef::Explorer(index),
+            position: (e.x, e.y),
+            cargo: None,
+            target: e.resource_position,
+        });
+    let extractors = map
+        .robot_extractors
+        .iter()
+        .enumerate()
+        .map(|(index, e)| RobotSnapshot {
+            robot: RobotRef::Extractor(index),
+            position: (e.x, e.y),
+            cargo: e.cargo,
+            target: e.target_position,
+        });
+    explorers.chain(extractors).collect()
+}
+
+fn actions(before: &[RobotSnapshot], after: &[RobotSnapshot]) -> Vec<Action> {
+    let mut actions = vec![];
+    for (old, new) in before.iter().zip(after) {
+        let robot = new.robot;
+        if old.position != new.position {
+            actions.push(Action::Move(robot, new.position));
+        }
+        match (old.cargo, new.cargo) {
+            (None, Some(kind)) => actions.push(Action::Collect(robot, kind)),
+            (Some(kind), None) => actions.push(Action::Deposit(robot, kind)),
+            _ => {}
+        }
+        match (robot, old.target, new.target) {
+            (RobotRef::Extractor(_), None, Some(site)) => actions.push(Action::Assign(robot, site)),
+            (RobotRef::Explorer(_), Some(site), None) => actions.push(Action::Report(robot, site)),
+            _ => {}
+        }
+    }
+    actions
+}
+
+// Empreinte FNV-1a de l'état simulé, stable d'une compilation à l'autre
+pub fn state_hash(map: &Map) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut feed = |value: u64| {
+        for byte in value.to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+
+    feed(map.tick as u64);
+    feed(map.collected as u64);
+    for e in &map.robot_explorers {
+        feed(e.x as u64);
+        feed(e.y as u64);
+        feed(e.battery.to_bits() as u64);
+        feed(e.health.to_bits() as u64);
+        feed(e.broken as u64);
+        feed(
+            e.resource_position
+                .map_or(u64::MAX, |(x, y)| (x * 65536 + y) as u64),
+        );
+    }
+    for e in &map.robot_extractors {
+        feed(e.x as u64);
+        feed(e.y as u64);
+        feed(e.battery.to_bits() as u64);
+        feed(e.cargo.map_or(0, |kind| kind as u64 + 1));
+        feed(e.health.to_bits() as u64);
+        feed(e.broken as u64);
+        feed(
+            e.target_position
+                .map_or(u64::MAX, |(x, y)| (x * 65536 + y) as u64),
+        );
+    }
+    feed(map.station.x as u64);
+    feed(map.station.y as u64);
+    feed(map.station.energy as u64);
+    feed(map.station.minerals as u64);
+    for &(x, y) in &map.station.pending_sites {
+        feed((x * 65536 + y) as u64);
+    }
+    for grid in [&map.energy, &map.minerals, &map.obstacles, &map.fog_of_war] {
+        for row in grid.iter() {
+            let mut bits = 0u64;
+            for (x, &cell) in row.iter().enumerate() {
+                bits ^= (cell as u64) << (x % 64);
+                if x % 64 == 63 {
+                    feed(bits);
+                    bits = 0;
+                }
+            }
+            feed(bits);
+        }
+    }
+    hash
+}
+
+// Joue un tick en appliquant d'abord les modifications manuelles et les ordres externes,
+// puis renvoie son enregistrement
+fn play_tick(
+    map: &mut Map,
+    edits: Vec<Edit>,
+    orders: Vec<(RobotRef, brain::Action)>,
+) -> TickRecord {
+    for edit in &edits {
+        map.paint(edit.x, edit.y, edit.brush);
+    }
+    map.orders = orders.clone();
+    let before = snapshot(map);
+    map.update_robot();
+    TickRecord {
+        tick: map.tick,
+        hash: state_hash(map),
+        edits,
+        orders,
+        actions: actions(&before, &snapshot(map)),
+    }
+}
+
+// Enregistre une mission en cours, tick après tick
+pub struct Recorder {
+    pub replay: Replay,
+    pending_edits: Vec<Edit>,
+}
+
+impl Recorder {
+    pub fn new(seed: u64, settings: Vec<(String, String)>, map: &Map) -> Self {
+        Recorder {
+            replay: Replay {
+                seed,
+                settings,
+                config: map.config.clone(),
+                dispatch_mode: map.dispatch_mode,
+                ticks: vec![],
+            },
+            pending_edits: vec![],
+        }
+    }
+
+    pub fn record_edit(&mut self, x: usize, y: usize, brush: Brush) {
+        self.pending_edits.push(Edit { x, y, brush });
+    }
+
+    // Les modifications sont déjà appliquées à la carte : elles ne sont que consignées
+    pub fn tick(&mut self, map: &mut Map) {
+        if map.game_over {
+            return;
+        }
+        let edits = std::mem::take(&mut self.pending_edits);
+        // Les ordres éventuels ont été déposés sur la carte par l'agent
+        let orders = std::mem::take(&mut map.orders);
+        let mut record = play_tick(map, vec![], orders);
+        record.edits = edits;
+        self.replay.ticks.push(record);
+    }
+}
+
+// Relecture d'une mission enregistrée, avec retour en arrière par images clés
+pub struct Playback {
+    pub replay: Replay,
+    keyframes: Vec<Map>,
+    // Premier tick dont l'empreinte ne correspond pas à l'enregistrement
+    pub diverged_at: Option<usize>,
+}
+
+impl Playback {
+    pub fn new(replay: Replay, map: &Map) -> Self {
+        Playback {
+            replay,
+            keyframes: vec![map.clone()],
+            diverged_at: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.replay.ticks.len()
+    }
+
+    pub fn tick(&mut self, map: &mut Map) {
+        // Les modifications enregistrées peuvent relancer une mission terminée
+        let record = match self.replay.ticks.get(map.tick) {
+            Some(record) => record,
+            None => return,
+        };
+        let played = play_tick(map, record.edits.clone(), record.orders.clone());
+        if played.hash != record.hash && self.diverged_at.is_none() {
+            self.diverged_at = Some(played.tick);
+        }
+        if map.tick.is_multiple_of(KEYFRAME_INTERVAL)
+            && self.keyframes.len() == map.tick / KEYFRAME_INTERVAL
+        {
+            self.keyframes.push(map.clone());
+        }
+    }
+
+    // Se place sur un tick quelconque en rejouant depuis l'image clé précédente
+    pub fn seek(&mut self, map: &mut Map, tick: usize) {
+        let tick = tick.min(self.len());
+        let keyframe = (tick / KEYFRAME_INTERVAL).min(self.keyframes.len() - 1);
+        let trace_searches = map.trace_searches;
+        *map = self.keyframes[keyframe].clone();
+        map.trace_searches = trace_searches;
+        map.damage = Damage::everything();
+        while map.tick < tick {
+            let before = map.tick;
+            self.tick(map);
+            if map.tick == before {
+                break;
+            }
+        }
+    }
+}
+
+impl Replay {
+    // Paramètres de la mission vérifiés comme ceux de la ligne de commande
+    fn checked_settings(&self) -> Result<GenerationSettings, String> {
+        self.config.validate()?;
+        let mut settings = GenerationSettings::default();
+        for (key, value) in &self.settings {
+            settings.set(key, value)?;
+        }
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    // Regénère la carte de départ de la mission
+    pub fn initial_map(&self) -> Result<Map, String> {
+        let settings = self.checked_settings()?;
+        let (mut map, _) = Map::generate(self.seed, &settings, &self.config);
+        map.dispatch_mode = self.dispatch_mode;
+        Ok(map)
+    }
+
+    // Resimule la mission et vérifie chaque tick ; renvoie le nombre de ticks vérifiés
+    pub fn validate(&self) -> Result<usize, String> {
+        let mut map = self.initial_map()?;
+        map.verbose = false;
+        for record in &self.ticks {
+            let played = play_tick(&mut map, record.edits.clone(), record.orders.clone());
+            if played.tick != record.tick {
+                return Err(format!(
+                    "expected tick {}, but the mission stopped at tick {}",
+                    record.tick, played.tick
+                ));
+            }
+            if played.actions != record.actions {
+                return Err(format!(
+                    "tick {}: expected actions {:?}, got {:?}",
+                    record.tick, record.actions, played.actions
+                ));
+            }
+            if played.hash != record.hash {
+                return Err(format!(
+                    "tick {}: state hash {:016x} differs from recorded {:016x}",
+                    record.tick, played.hash, record.hash
+                ));
+            }
+        }
+        Ok(self.ticks.len())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut out = String::new();
+        let _ = writeln!(out, "{}", REPLAY_HEADER);
+        let _ = writeln!(out, "seed {}", self.seed);
+        let _ = writeln!(out, "dispatch {:?}", self.dispatch_mode);
+        let config = &self.config;
+        let _ = writeln!(
+            out,
+            "config {} {} {} {} {}",
+            config.map_size,
+            config.explorers,
+            config.extractors,
+            config.max_ticks,
+            config.stall_ticks
+        );
+        let robots = &config.robots;
+        let _ = writeln!(
+            out,
+            "robots {} {} {} {} {} {} {}",
+            robots.battery_capacity,
+            robots.battery_recharge,
+            robots.low_battery,
+            robots.max_climb,
+            robots.max_loaded_climb,
+            robots.sensor_range,
+            robots.breakdown_rate
+        );
+        let _ = writeln!(out, "strategy {}", config.strategy.label());
+        let _ = match config.station {
+            StationPlacement::Random => writeln!(out, "station random"),
+            StationPlacement::Center => writeln!(out, "station center"),
+            StationPlacement::Fixed(x, y) => writeln!(out, "station {},{}", x, y),
+        };
+        let _ = match config.victory {
+            Victory::AllResources => writeln!(out, "victory all"),
+            Victory::Stockpile { energy, minerals } => {
+                writeln!(out, "victory stockpile {} {}", energy, minerals)
+            }
+            Victory::Endless => writeln!(out, "victory endless"),
+        };
+        // Le script n'est pas copié : la relecture le recharge depuis son chemin absolu
+        // et vérifie qu'il n'a pas changé grâce à son empreinte
+        for (kind, brain) in [
+            ("explorer", &config.scripts.explorer),
+            ("extractor", &config.scripts.extractor),
+        ] {
+            if let Some(brain) = brain {
+                let script =
+                    std::path::absolute(&brain.path).unwrap_or_else(|_| brain.path.clone());
+                let _ = writeln!(
+                    out,
+                    "script {} {} {:016x} {}",
+                    kind,
+                    brain.budget,
+                    brain.source_hash,
+                    script.display()
+                );
+            }
+        }
+        let day = &config.day;
+        if day.is_enabled() {
+            let _ = writeln!(
+                out,
+                "day {} {} {} {}",
+                day.length, day.night, day.night_sensor_range, day.night_recharge
+            );
+        }
+        let hazards = &config.hazards;
+        if *hazards != Hazards::default() {
+            let _ = writeln!(
+                out,
+                "hazards {} {} {} {}",
+                hazards.storm_rate,
+                hazards.rockfall_rate,
+                hazards.hazard_rate,
+                hazards.hazard_damage
+            );
+        }
+        let growth = &config.growth;
+        if growth.is_enabled() {
+            let _ = writeln!(
+                out,
+                "growth {} {} {}",
+                growth.regrow_ticks, growth.spawn_rate, growth.spawn_frequency
+            );
+        }
+        for scheduled in &hazards.scheduled {
+            let _ = writeln!(out, "event {} {}", scheduled.tick, scheduled.event);
+        }
+        for (key, value) in &self.settings {
+            let _ = writeln!(out, "setting {} {}", key, value);
+        }
+        for record in &self.ticks {
+            for edit in &record.edits {
+                let _ = writeln!(out, "edit {},{} {}", edit.x, edit.y, edit.brush.label());
+            }
+            for (robot, order) in &record.orders {
+                let _ = writeln!(out, "order {} {}", format_robot(robot), order);
+            }
+            let _ = write!(out, "{} {:016x}", record.tick, record.hash);
+            for action in &record.actions {
+                let _ = write!(out, " {}", format_action(action));
+            }
+            out.push('\n');
+        }
+        fs::write(path, out).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let text =
+            fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        let mut lines = text.lines().enumerate();
+        match lines.next() {
+            Some((_, REPLAY_HEADER)) => {}
+            _ => return Err(format!("{}: not a replay file", path.display())),
+        }
+
+        let mut replay = Replay {
+            seed: 0,
+            settings: vec![],
+            config: MissionConfig::default(),
+            dispatch_mode: DispatchMode::Concurrent,
+            ticks: vec![],
+        };
+        let mut edits = vec![];
+        let mut orders = vec![];
+        // Lignes sans valeur par défaut
+        let mut missing = vec!["seed", "dispatch", "config", "robots"];
+        for (number, line) in lines {
+            let invalid = || format!("{}:{}: invalid line '{}'", path.display(), number + 1, line);
+            missing.retain(|&keyword| line.split_whitespace().next() != Some(keyword));
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("seed") => {
+                    replay.seed = fields
+                        .next()
+                        .and_then(|s| s.parse().ok())
+                        .ok_or_else(invalid)?
+                }
+                Some("dispatch") => {
+                    replay.dispatch_mode = match fields.next() {
+                        Some("Serialized") => DispatchMode::Serialized,
+                        Some("Concurrent") => DispatchMode::Concurrent,
+                        _ => return Err(invalid()),
+                    }
+                }
+                Some("config") => {
+                    let values = fields
+                        .map(|field| field.parse().ok())
+                        .collect::<Option<Vec<usize>>>()
+                        .ok_or_else(invalid)?;
+                    let (map_size, explorers, extractors, max_ticks, stall_ticks) = match values[..]
+                    {
+                        [a, b, c, d, e] => (a, b, c, d, e),
+                        _ => return Err(invalid()),
+                    };
+                    replay.config = MissionConfig {
+                        map_size,
+                        explorers,
+                        extractors,
+                        max_ticks,
+                        stall_ticks,
+                        ..replay.config
+                    };
+                }
+                Some("robots") => {
+                    let values: Vec<&str> = fields.collect();
+                    let robots = match values[..] {
+                        [capacity, recharge, low, climb, loaded, range, breakdown] => {
+                            Capabilities {
+                                battery_capacity: capacity.parse().map_err(|_| invalid())?,
+                                battery_recharge: recharge.parse().map_err(|_| invalid())?,
+                                low_battery: low.parse().map_err(|_| invalid())?,
+                                max_climb: climb.parse().map_err(|_| invalid())?,
+                                max_loaded_climb: loaded.parse().map_err(|_| invalid())?,
+                                sensor_range: range.parse().map_err(|_| invalid())?,
+                                breakdown_rate: breakdown.parse().map_err(|_| invalid())?,
+                            }
+                        }
+                        _ => return Err(invalid()),
+                    };
+                    replay.config.robots = robots;
+                }
+                Some("strategy") => {
+                    replay.config.strategy = fields
+                        .next()
+                        .and_then(|label| ExplorerStrategy::parse(label).ok())
+                        .ok_or_else(invalid)?
+                }
+                Some("station") => {
+                    replay.config.station = match fields.next() {
+                        Some("random") => StationPlacement::Random,
+                        Some("center") => StationPlacement::Center,
+                        Some(cell) => {
+                            let (x, y) = parse_cell(cell).ok_or_else(invalid)?;
+                            StationPlacement::Fixed(x, y)
+                        }
+                        None => return Err(invalid()),
+                    }
+                }
+                Some("victory") => {
+                    replay.config.victory = match (fields.next(), fields.next(), fields.next()) {
+                        (Some("all"), None, None) => Victory::AllResources,
+                        (Some("endless"), None, None) => Victory::Endless,
+                        (Some("stockpile"), Some(energy), Some(minerals)) => Victory::Stockpile {
+                            energy: energy.parse().map_err(|_| invalid())?,
+                            minerals: minerals.parse().map_err(|_| invalid())?,
+                        },
+                        _ => return Err(invalid()),
+                    }
+                }
+                Some("script") => {
+                    // Le chemin peut contenir des espaces
+                    let mut parts = line.splitn(5, ' ').skip(1);
+                    let (kind, budget, hash, script) =
+                        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+                            (Some(kind), Some(budget), Some(hash), Some(script)) => {
+                                (kind, budget, hash, script)
+                            }
+                            _ => return Err(invalid()),
+                        };
+                    let budget = budget.parse().map_err(|_| invalid())?;
+                    let hash = u64::from_str_radix(hash, 16).map_err(|_| invalid())?;
+                    let brain = Brain::load(Path::new(script), budget)?;
+                    if hash != brain.source_hash {
+                        return Err(format!(
+                            "{}:{}: the {} script {} has changed since the replay was recorded",
+                            path.display(),
+                            number + 1,
+                            kind,
+                            script
+                        ));
+                    }
+                    let brain = Some(brain);
+                    match kind {
+                        "explorer" => replay.config.scripts.explorer = brain,
+                        "extractor" => replay.config.scripts.extractor = brain,
+                        _ => return Err(invalid()),
+                    }
+                }
+                Some("day") => {
+                    let values: Vec<&str> = fields.collect();
+                    let day = &mut replay.config.day;
+                    match values[..] {
+                        [length, night, range, recharge] => {
+                            day.length = length.parse().map_err(|_| invalid())?;
+                            day.night = night.parse().map_err(|_| invalid())?;
+                            day.night_sensor_range = range.parse().map_err(|_| invalid())?;
+                            day.night_recharge = recharge.parse().map_err(|_| invalid())?;
+                        }
+                        _ => return Err(invalid()),
+                    }
+                }
+                Some("hazards") => {
+                    let values: Vec<&str> = fields.collect();
+                    let hazards = &mut replay.config.hazards;
+                    match values[..] {
+                        [storm, rockfall, hazard, damage] => {
+                            hazards.storm_rate = storm.parse().map_err(|_| invalid())?;
+                            hazards.rockfall_rate = rockfall.parse().map_err(|_| invalid())?;
+                            hazards.hazard_rate = hazard.parse().map_err(|_| invalid())?;
+                            hazards.hazard_damage = damage.parse().map_err(|_| invalid())?;
+                        }
+                        _ => return Err(invalid()),
+                    }
+                }
+                Some("growth") => {
+                    let values: Vec<&str> = fields.collect();
+                    let growth = &mut replay.config.growth;
+                    match values[..] {
+                        [regrow, rate, frequency] => {
+                            growth.regrow_ticks = regrow.parse().map_err(|_| invalid())?;
+                            growth.spawn_rate = rate.parse().map_err(|_| invalid())?;
+                            growth.spawn_frequency = frequency.parse().map_err(|_| invalid())?;
+                        }
+                        _ => return Err(invalid()),
+                    }
+                }
+                Some("event") => {
+                    let tick = fields
+                        .next()
+                        .and_then(|tick| tick.parse().ok())
+                        .ok_or_else(invalid)?;
+                    let words: Vec<&str> = fields.collect();
+                    let event = Event::parse(&words).ok_or_else(invalid)?;
+                    replay
+                        .config
+                        .hazards
+                        .scheduled
+                        .push(ScheduledEvent { tick, event });
+                }
+                Some("setting") => match (fields.next(), fields.next()) {
+                    (Some(key), Some(value)) => {
+                        replay.settings.push((key.to_string(), value.to_string()))
+                    }
+                    _ => return Err(invalid()),
+                },
+                Some("edit") => {
+                    let (x, y) = fields.next().and_then(parse_cell).ok_or_else(invalid)?;
+                    let brush = fields
+                        .next()
+                        .and_then(|label| Brush::ALL.into_iter().find(|b| b.label() == label))
+                        .ok_or_else(invalid)?;
+                    edits.push(Edit { x, y, brush });
+                }
+                Some("order") => {
+                    let robot = fields.next().and_then(parse_robot).ok_or_else(invalid)?;
+                    let words: Vec<&str> = fields.collect();
+                    let order = brain::Action::parse(&words).ok_or_else(invalid)?;
+                    orders.push((robot, order));
+                }
+                Some(tick) => {
+                    let tick = tick.parse().map_err(|_| invalid())?;
+                    let hash = fields
+                        .next()
+                        .and_then(|hash| u64::from_str_radix(hash, 16).ok())
+                        .ok_or_else(invalid)?;
+                    let actions = fields
+                        .map(parse_action)
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(invalid)?;
+                    replay.ticks.push(TickRecord {
+                        tick,
+                        hash,
+                        edits: std::mem::take(&mut edits),
+                        orders: std::mem::take(&mut orders),
+                        actions,
+                    });
+                }
+                None => {}
+            }
+        }
+        if let Some(keyword) = missing.first() {
+            return Err(format!("{}: missing '{}' line", path.display(), keyword));
+        }
+        replay.checked_settings().map_err(|error| {
+            let name = path.display();
+            format!(
+                "{}: {}",
+                name,
+                error.replace('\n', &format!("\n{}: ", name))
+            )
+        })?;
+        Ok(replay)
+    }
+}
+
+// Robots désignés par `e0` pour un explorateur, `x1` pour un extracteur
+fn format_robot(robot: &RobotRef) -> String {
+    match robot {
+        RobotRef::Explorer(index) => format!("e{}", index),
+        RobotRef::Extractor(index) => format!("x{}", index),
+    }
+}
+
+fn parse_robot(text: &str) -> Option<RobotRef> {
+    let index = text.get(1..)?.parse().ok()?;
+    match text.chars().next()? {
+        'e' => Some(RobotRef::Explorer(index)),
+        'x' => Some(RobotRef::Extractor(index)),
+        _ => None,
+    }
+}
+
+// Format compact des actions : `e0>3,4` déplacement, `x1+E` collecte, `x1-M` dépôt,
+// `e0!3,4` signalement d'un site, `x0@3,4` attribution d'un site
+fn format_action(action: &Action) -> String {
+    let robot = format_robot;
+    let kind = |kind: &ResourceKind| match kind {
+        ResourceKind::Energy => 'E',
+        ResourceKind::Minerals => 'M',
+    };
+    match action {
+        Action::Move(r, (x, y)) => format!("{}>{},{}", robot(r), x, y),
+        Action::Collect(r, k) => format!("{}+{}", robot(r), kind(k)),
+        Action::Deposit(r, k) => format!("{}-{}", robot(r), kind(k)),
+        Action::Report(r, (x, y)) => format!("{}!{},{}", robot(r), x, y),
+        Action::Assign(r, (x, y)) => format!("{}@{},{}", robot(r), x, y),
+    }
+}
+
+fn parse_cell(text: &str) -> Option<(usize, usize)> {
+    let (x, y) = text.split_once(',')?;
+    Some((x.parse().ok()?, y.parse().ok()?))
+}
+
+fn parse_action(token: &str) -> Option<Action> {
+    let split = token.find(['>', '+', '-', '!', '@'])?;
+    let (robot, rest) = token.split_at(split);
+    let robot = parse_robot(robot)?;
+    let kind = |text: &str| match text {
+        "E" => Some(ResourceKind::Energy),
+        "M" => Some(ResourceKind::Minerals),
+        _ => None,
+    };
+    let (op, arg) = rest.split_at(1);
+    match op {
+        ">" => Some(Action::Move(robot, parse_cell(arg)?)),
+        "+" => Some(Action::Collect(robot, kind(arg)?)),
+        "-" => Some(Action::Deposit(robot, kind(arg)?)),
+        "!" => Some(Action::Report(robot, parse_cell(arg)?)),
+        "@" => Some(Action::Assign(robot, parse_cell(arg)?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICKS: usize = 250;
+
+    // Mission courte enregistrée sans affichage, avec une modification manuelle au tick 20 ;
+    // renvoie aussi l'empreinte relevée après chaque tick
+    fn recorded_mission(seed: u64) -> (Replay, Vec<u64>) {
+        let (mut map, _) = Map::generate(
+            seed,
+            &GenerationSettings::default(),
+            &MissionConfig::default(),
+        );
+        map.verbose = false;
+        let mut recorder = Recorder::new(seed, vec![], &map);
+        let size = map.size;
+        let mut hashes = vec![];
+        while !map.game_over && map.tick < TICKS {
+            if map.tick == 20 {
+                let cell = (0..map.size)
+                    .flat_map(|y| (0..size).map(move |x| (x, y)))
+                    .find(|&(x, y)| map.paint(x, y, Brush::Energy))
+                    .expect("a cell accepts the energy brush");
+                recorder.record_edit(cell.0, cell.1, Brush::Energy);
+            }
+            recorder.tick(&mut map);
+            hashes.push(state_hash(&map));
+        }
+        (recorder.replay, hashes)
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("cosmobots-{}-{}.replay", name, std::process::id()))
+    }
+
+    #[test]
+    fn save_load_validate_round_trip() {
+        let (replay, hashes) = recorded_mission(7);
+        assert_eq!(replay.ticks.len(), hashes.len());
+        assert_eq!(replay.ticks.iter().map(|t| t.edits.len()).sum::<usize>(), 1);
+
+        let path = temp_path("round-trip");
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.seed, replay.seed);
+        assert_eq!(loaded.dispatch_mode, replay.dispatch_mode);
+        assert_eq!(loaded.config, replay.config);
+        assert_eq!(loaded.ticks.len(), replay.ticks.len());
+        for (loaded, recorded) in loaded.ticks.iter().zip(&replay.ticks) {
+            assert_eq!(loaded.tick, recorded.tick);
+            assert_eq!(loaded.hash, recorded.hash);
+            assert_eq!(loaded.edits, recorded.edits);
+            assert_eq!(loaded.actions, recorded.actions);
+        }
+        assert_eq!(loaded.validate(), Ok(replay.ticks.len()));
+    }
+
+    #[test]
+    fn load_rejects_a_modified_script() {
+        let script = temp_path("script").with_extension("rhai");
+        fs::write(&script, "fn decide(robot, view) { wait() }").unwrap();
+        let config = MissionConfig {
+            scripts: brain::Scripts {
+                explorer: Some(Brain::load(&script, brain::DEFAULT_SCRIPT_BUDGET).unwrap()),
+                extractor: None,
+            },
+            ..MissionConfig::default()
+        };
+        let (map, _) = Map::generate(5, &GenerationSettings::default(), &config);
+        let path = temp_path("script");
+        Recorder::new(5, vec![], &map).replay.save(&path).unwrap();
+        let unchanged = Replay::load(&path).map(|replay| replay.config.scripts.explorer);
+
+        fs::write(&script, "fn decide(robot, view) { go_home() }").unwrap();
+        let modified = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&script).unwrap();
+
+        assert_eq!(unchanged.unwrap(), config.scripts.explorer);
+        let error = modified.unwrap_err();
+        assert!(
+            error.ends_with("has changed since the replay was recorded"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn load_rejects_an_invalid_config() {
+        let (map, _) = Map::generate(5, &GenerationSettings::default(), &MissionConfig::default());
+        let mut replay = Recorder::new(5, vec![], &map).replay;
+        replay.config.explorers = 0;
+        replay.config.station = StationPlacement::Fixed(3, 40);
+        replay.settings = vec![("elevation.octaves".to_string(), "0".to_string())];
+
+        let path = temp_path("invalid-config");
+        replay.save(&path).unwrap();
+        let error = Replay::load(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        let name = path.display();
+        assert_eq!(
+            error,
+            format!(
+                "{name}: at least one explorer is required\n{name}: station position (3, 40) is outside the 10x10 map"
+            )
+        );
+        assert!(replay.initial_map().is_err());
+
+        replay.config = MissionConfig::default();
+        assert_eq!(
+            replay.initial_map().err().as_deref(),
+            Some("elevation: octaves must be between 1 and 32, got 0")
+        );
+    }
+
+    #[test]
+    fn load_rejects_other_formats() {
+        let path = temp_path("old-format");
+        fs::write(&path, "cosmobots-replay 1\nseed 5\n").unwrap();
+        let error = Replay::load(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(error, format!("{}: not a replay file", path.display()));
+    }
+
+    #[test]
+    fn load_requires_every_config_field() {
+        let (map, _) = Map::generate(5, &GenerationSettings::default(), &MissionConfig::default());
+        let path = temp_path("fields");
+        Recorder::new(5, vec![], &map).replay.save(&path).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        // Numéro et contenu de la ligne qui commence par `keyword`
+        let line = |keyword: &str| {
+            text.lines()
+                .enumerate()
+                .find(|(_, line)| line.starts_with(keyword))
+                .map(|(index, line)| (index + 1, line))
+                .unwrap()
+        };
+
+        // Un champ en moins, puis une ligne en moins
+        let (number, config) = line("config ");
+        let truncated = config.rsplit_once(' ').unwrap().0;
+        fs::write(&path, text.replace(config, truncated)).unwrap();
+        let short_field = Replay::load(&path);
+        let (_, robots) = line("robots ");
+        fs::write(&path, text.replace(&format!("{}\n", robots), "")).unwrap();
+        let missing_line = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            short_field.unwrap_err(),
+            format!(
+                "{}:{}: invalid line '{}'",
+                path.display(),
+                number,
+                truncated
+            )
+        );
+        assert_eq!(
+            missing_line.unwrap_err(),
+            format!("{}: missing 'robots' line", path.display())
+        );
+    }
+
+    #[test]
+    fn health_is_part_of_the_state_hash() {
+        let (mut map, _) =
+            Map::generate(5, &GenerationSettings::default(), &MissionConfig::default());
+        let hash = state_hash(&map);
+        map.robot_extractors[0].health -= 1.0;
+        let damaged = state_hash(&map);
+        map.robot_extractors[0].broken = true;
+        assert_ne!(hash, damaged);
+        assert_ne!(damaged, state_hash(&map));
+    }
+
+    #[test]
+    fn validate_reports_a_tampered_hash() {
+        let (mut replay, _) = recorded_mission(7);
+        replay.ticks[30].hash ^= 1;
+        let error = replay.validate().unwrap_err();
+        assert!(error.starts_with("tick 31: state hash"), "{}", error);
+    }
+
+    #[test]
+    fn seeking_reproduces_recorded_hashes() {
+        let (replay, hashes) = recorded_mission(11);
+        assert_eq!(hashes.len(), TICKS);
+        let mut map = replay.initial_map().unwrap();
+        map.verbose = false;
+        let start = state_hash(&map);
+        let mut playback = Playback::new(replay, &map);
+
+        // En avant au-delà de deux images clés, en arrière, puis de nouveau en avant
+        for tick in [hashes.len(), 50, 150, 0, 120] {
+            playback.seek(&mut map, tick);
+            assert_eq!(map.tick, tick);
+            let expected = if tick == 0 { start } else { hashes[tick - 1] };
+            assert_eq!(state_hash(&map), expected, "after seeking to tick {}", tick);
+        }
+        assert_eq!(playback.diverged_at, None);
+    }
+}
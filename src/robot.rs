@@ -1,8 +1,15 @@
 use rand::prelude::*;
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 
-use crate::{heuristic, Map, Node, DIRECTIONS, MAP_SIZE};
+use crate::brain::BrainState;
+use crate::camera::RobotRef;
+use crate::map::grid;
+use crate::metrics::SearchStats;
+use crate::mission::Capabilities;
+use crate::station::ResourceKind;
+use crate::{heuristic, neighbors, Map, Node, MAX_HEALTH, MIN_STEP_COST};
 
+#[derive(Clone)]
 pub struct RobotExplorer {
     pub x: usize,
     pub y: usize,
@@ -10,100 +17,150 @@ pub struct RobotExplorer {
     pub station_y: usize,
     pub founded_resource: bool,
     pub resource_position: Option<(usize, usize)>,
-    pub waiting: bool,
+    pub reported_sites: HashSet<(usize, usize)>,
+    pub battery: f32,
+    pub move_progress: usize,
+    pub health: f32,
+    // Immobilisé jusqu'à ce qu'un extracteur vienne le réparer
+    pub broken: bool,
+    // Cases inexplorées envisagées au dernier déplacement aléatoire
+    pub frontier: Vec<(usize, usize)>,
+    // Case voisine tirée au hasard, conservée jusqu'à ce que l'effort accumulé suffise
+    pub wander_target: Option<(usize, usize)>,
+    pub last_search: Option<SearchTrace>,
+    // Recherches du tick courant, relevées par les métriques
+    pub searches: SearchStats,
+    // Mémoire du script qui pilote le robot, s'il y en a un
+    pub brain: BrainState,
 }
 
+#[derive(Clone)]
 pub struct RobotExtractor {
     pub x: usize,
     pub y: usize,
     pub station_x: usize,
     pub station_y: usize,
-    pub carrying_resource: bool,
+    pub cargo: Option<ResourceKind>,
     pub target_position: Option<(usize, usize)>,
-    pub waiting: bool,
+    pub battery: f32,
+    pub move_progress: usize,
+    pub health: f32,
+    pub broken: bool,
+    // Robot en panne à dépanner et sa position
+    pub rescue: Option<(RobotRef, (usize, usize))>,
+    pub last_search: Option<SearchTrace>,
+    pub searches: SearchStats,
+    pub brain: BrainState,
+}
+
+// Trace de la dernière recherche A* d'un robot, pour le calque de débogage
+#[derive(Clone, Debug)]
+pub struct SearchTrace {
+    pub goal: (usize, usize),
+    pub path: Vec<(usize, usize)>,
+    pub open: Vec<(usize, usize)>,
+    pub closed: Vec<(usize, usize)>,
+}
+
+// Recherche un chemin, la comptabilise et conserve sa trace si la carte le demande
+fn planned_path(
+    map: &Map,
+    start: (usize, usize),
+    goal: (usize, usize),
+    loaded: bool,
+    last_search: &mut Option<SearchTrace>,
+    searches: &mut SearchStats,
+) -> Option<Vec<(usize, usize)>> {
+    if !map.trace_searches {
+        let (path, expanded) = search_path(map, start, goal, loaded, None);
+        searches.record(path.is_some(), expanded);
+        return path;
+    }
+    let mut trace = SearchTrace {
+        goal,
+        path: vec![],
+        open: vec![],
+        closed: vec![],
+    };
+    let (path, expanded) = search_path(map, start, goal, loaded, Some(&mut trace));
+    searches.record(path.is_some(), expanded);
+    trace.path = path.clone().unwrap_or_default();
+    *last_search = Some(trace);
+    path
 }
 
 impl RobotExtractor {
-    pub fn new(station_x: usize, station_y: usize) -> Self {
+    pub fn new(station_x: usize, station_y: usize, battery: f32) -> Self {
         RobotExtractor {
             x: station_x,
             y: station_y,
             station_x,
             station_y,
-            carrying_resource: false,
+            cargo: None,
             target_position: None,
-            waiting: false, // Initialisation
+            battery,
+            move_progress: 0,
+            health: MAX_HEALTH,
+            broken: false,
+            rescue: None,
+            last_search: None,
+            searches: SearchStats::default(),
+            brain: BrainState::default(),
         }
     }
 
+    pub fn is_at_station(&self) -> bool {
+        self.x == self.station_x && self.y == self.station_y
+    }
+
+    // Un extracteur est occupé tant qu'il a une cible, une ressource à ramener ou un robot à dépanner
+    pub fn is_busy(&self) -> bool {
+        self.cargo.is_some() || self.target_position.is_some() || self.rescue.is_some()
+    }
+
     pub fn move_towards(
         &mut self,
         target_x: usize,
         target_y: usize,
-        obstacles: &[[bool; MAP_SIZE]; MAP_SIZE],
-        fog_of_war: &[[bool; MAP_SIZE]; MAP_SIZE],
+        map: &Map,
     ) -> Option<Vec<(usize, usize)>> {
-        let start = (self.x, self.y);
-        let goal = (target_x, target_y);
-
-        let mut open_list = BinaryHeap::new();
-        let mut came_from = HashMap::new();
-        let mut g_score = vec![vec![usize::MAX; MAP_SIZE]; MAP_SIZE];
-        let mut f_score = vec![vec![usize::MAX; MAP_SIZE]; MAP_SIZE];
-
-        g_score[start.1][start.0] = 0;
-        f_score[start.1][start.0] = heuristic(start.0, start.1, goal.0, goal.1);
-
-        open_list.push(Node::new(start.0, start.1, 0, f_score[start.1][start.0]));
-
-        while let Some(current) = open_list.pop() {
-            if current.x == goal.0 && current.y == goal.1 {
-                let mut path = vec![];
-                let mut current_pos = (current.x, current.y);
-                while let Some(&prev) = came_from.get(&current_pos) {
-                    path.push(current_pos);
-                    current_pos = prev;
-                }
-                path.push(start);
-                path.reverse();
-                return Some(path);
-            }
-
-            for &(dx, dy) in &DIRECTIONS {
-                let neighbor_x =
-                    (current.x as isize + dx).max(0).min(MAP_SIZE as isize - 1) as usize;
-                let neighbor_y =
-                    (current.y as isize + dy).max(0).min(MAP_SIZE as isize - 1) as usize;
-                if obstacles[neighbor_y][neighbor_x] || fog_of_war[neighbor_y][neighbor_x] {
-                    continue;
-                }
-                let tentative_g_score = g_score[current.y][current.x] + 1;
-                if tentative_g_score < g_score[neighbor_y][neighbor_x] {
-                    came_from.insert((neighbor_x, neighbor_y), (current.x, current.y));
-                    g_score[neighbor_y][neighbor_x] = tentative_g_score;
-                    f_score[neighbor_y][neighbor_x] =
-                        tentative_g_score + heuristic(neighbor_x, neighbor_y, goal.0, goal.1);
-                    open_list.push(Node::new(
-                        neighbor_x,
-                        neighbor_y,
-                        tentative_g_score,
-                        f_score[neighbor_y][neighbor_x],
-                    ));
-                }
-            }
-        }
+        planned_path(
+            map,
+            (self.x, self.y),
+            (target_x, target_y),
+            self.cargo.is_some(),
+            &mut self.last_search,
+            &mut self.searches,
+        )
+    }
 
-        None
+    pub fn collect_resource(&mut self, kind: ResourceKind) {
+        self.cargo = Some(kind);
     }
 
-    pub fn collect_resource(&mut self) {
-        self.carrying_resource = true;
-        println!("Collected resource at ({}, {})", self.x, self.y);
+    // État courant, affiché dans le HUD
+    pub fn status(&self, robots: &Capabilities) -> &'static str {
+        if self.broken {
+            return "broken down";
+        }
+        if self.rescue.is_some() {
+            return "rescuing";
+        }
+        match (self.cargo, self.target_position) {
+            (Some(ResourceKind::Energy), _) => "hauling energy",
+            (Some(ResourceKind::Minerals), _) => "hauling minerals",
+            (None, Some(_)) => "heading to site",
+            (None, None) if self.is_at_station() && self.battery < robots.battery_capacity => {
+                "recharging"
+            }
+            (None, None) if self.is_at_station() => "idle",
+            (None, None) => "returning to station",
+        }
     }
 }
 
 impl RobotExplorer {
-    pub fn new(station_x: usize, station_y: usize) -> Self {
+    pub fn new(station_x: usize, station_y: usize, battery: f32) -> Self {
         RobotExplorer {
             x: station_x,
             y: station_y,
@@ -111,125 +168,346 @@ impl RobotExplorer {
             station_y,
             founded_resource: false,
             resource_position: None,
-            waiting: false,
+            reported_sites: HashSet::new(),
+            battery,
+            move_progress: 0,
+            health: MAX_HEALTH,
+            broken: false,
+            frontier: vec![],
+            wander_target: None,
+            last_search: None,
+            searches: SearchStats::default(),
+            brain: BrainState::default(),
         }
     }
 
-    pub fn move_random(&mut self, rng: &mut impl Rng, map: &mut Map) {
-        let mut possible_moves = vec![];
+    pub fn is_at_station(&self) -> bool {
+        self.x == self.station_x && self.y == self.station_y
+    }
 
-        for &(dx, dy) in &DIRECTIONS {
-            let new_x = (self.x as isize + dx).max(0).min(MAP_SIZE as isize - 1) as usize;
-            let new_y = (self.y as isize + dy).max(0).min(MAP_SIZE as isize - 1) as usize;
+    // État courant, affiché dans le HUD
+    pub fn status(&self, robots: &Capabilities) -> &'static str {
+        if self.broken {
+            "broken down"
+        } else if self.is_at_station() && self.battery < robots.battery_capacity {
+            "recharging"
+        } else if self.resource_position.is_some() {
+            "reporting site"
+        } else if self.battery < robots.low_battery {
+            "returning to recharge"
+        } else if self.is_at_station() {
+            "at station"
+        } else {
+            "exploring"
+        }
+    }
 
+    pub fn move_random(&mut self, map: &mut Map) {
+        let mut possible_moves = vec![];
+
+        for (new_x, new_y) in neighbors(self.x, self.y, map.size) {
             // Ne pas ajouter la station comme un mouvement possible
             if (new_x, new_y) == (self.station_x, self.station_y) {
                 continue;
             }
 
-            if !map.obstacles[new_y][new_x] && !map.explored[new_y][new_x] {
+            if map
+                .step_cost((self.x, self.y), (new_x, new_y), false)
+                .is_some()
+                && !map.explored[new_y][new_x]
+            {
                 possible_moves.push((new_x, new_y));
             }
         }
 
-        if possible_moves.is_empty() {
-            for &(dx, dy) in &DIRECTIONS {
-                let new_x = (self.x as isize + dx).max(0).min(MAP_SIZE as isize - 1) as usize;
-                let new_y = (self.y as isize + dy).max(0).min(MAP_SIZE as isize - 1) as usize;
+        self.frontier = possible_moves.clone();
 
+        if possible_moves.is_empty() {
+            for (new_x, new_y) in neighbors(self.x, self.y, map.size) {
                 // Ne pas ajouter la station comme un mouvement possible
                 if (new_x, new_y) == (self.station_x, self.station_y) {
                     continue;
                 }
 
-                if !map.obstacles[new_y][new_x] {
+                if map
+                    .step_cost((self.x, self.y), (new_x, new_y), false)
+                    .is_some()
+                {
                     possible_moves.push((new_x, new_y));
                 }
             }
         }
 
-        if let Some(&(new_x, new_y)) = possible_moves.choose(rng) {
-            self.x = new_x;
-            self.y = new_y;
-            map.explored[new_y][new_x] = true;
-
-            for dy in -1..=1 {
-                for dx in -1..=1 {
-                    let neighbor_x =
-                        (new_x as isize + dx).max(0).min(MAP_SIZE as isize - 1) as usize;
-                    let neighbor_y =
-                        (new_y as isize + dy).max(0).min(MAP_SIZE as isize - 1) as usize;
-                    map.fog_of_war[neighbor_y][neighbor_x] = false;
-                }
+        // La case visée reste la même tant qu'elle est encore voisine et praticable,
+        // afin que l'effort déjà accumulé paie bien la case où l'explorateur entre
+        let target = match self.wander_target {
+            Some((x, y))
+                if self.x.abs_diff(x).max(self.y.abs_diff(y)) == 1
+                    && map.step_cost((self.x, self.y), (x, y), false).is_some() =>
+            {
+                Some((x, y))
             }
+            _ => {
+                self.move_progress = 0;
+                possible_moves.choose(&mut map.rng).copied()
+            }
+        };
+        self.wander_target = target;
 
-            if map.energy[new_y][new_x] {
-                self.founded_resource = true;
-                self.resource_position = Some((new_x, new_y));
-                println!("Founded energy at ({}, {}).", new_x, new_y);
-            } else if map.minerals[new_y][new_x] {
-                self.founded_resource = true;
-                self.resource_position = Some((new_x, new_y));
-                println!("Founded minerals at ({}, {}).", new_x, new_y);
+        if let Some((new_x, new_y)) = target {
+            let path = [(self.x, self.y), (new_x, new_y)];
+            if map
+                .step_along(&path, false, &mut self.move_progress, &mut self.battery)
+                .is_none()
+            {
+                return;
             }
+            self.wander_target = None;
+            self.visit(map, new_x, new_y);
         }
     }
 
-    pub fn return_to_station(
-        &mut self,
-        obstacles: &[[bool; MAP_SIZE]; MAP_SIZE],
-        fog_of_war: &[[bool; MAP_SIZE]; MAP_SIZE],
-    ) -> Option<Vec<(usize, usize)>> {
-        let start = (self.x, self.y);
-        let goal = (self.station_x, self.station_y);
-
-        let mut open_list = BinaryHeap::new();
-        let mut came_from = HashMap::new();
-        let mut g_score = vec![vec![usize::MAX; MAP_SIZE]; MAP_SIZE];
-        let mut f_score = vec![vec![usize::MAX; MAP_SIZE]; MAP_SIZE];
-
-        g_score[start.1][start.0] = 0;
-        f_score[start.1][start.0] = heuristic(start.0, start.1, goal.0, goal.1);
-
-        open_list.push(Node::new(start.0, start.1, 0, f_score[start.1][start.0]));
-
-        while let Some(current) = open_list.pop() {
-            if current.x == goal.0 && current.y == goal.1 {
-                let mut path = vec![];
-                let mut current_pos = (current.x, current.y);
-                while let Some(&prev) = came_from.get(&current_pos) {
-                    path.push(current_pos);
-                    current_pos = prev;
+    // Se dirige vers la case inexplorée accessible la plus proche, ou au hasard s'il n'en reste aucune
+    pub fn move_to_frontier(&mut self, map: &mut Map) {
+        let target = match self.nearest_unexplored(map) {
+            Some(target) => target,
+            None => return self.move_random(map),
+        };
+        self.frontier = vec![target];
+
+        let path = match planned_path(
+            map,
+            (self.x, self.y),
+            target,
+            false,
+            &mut self.last_search,
+            &mut self.searches,
+        ) {
+            Some(path) => path,
+            None => return,
+        };
+        if let Some((new_x, new_y)) =
+            map.step_along(&path, false, &mut self.move_progress, &mut self.battery)
+        {
+            self.visit(map, new_x, new_y);
+        }
+    }
+
+    // Parcours en largeur des cases praticables jusqu'à la première case inexplorée
+    fn nearest_unexplored(&self, map: &Map) -> Option<(usize, usize)> {
+        let station = (self.station_x, self.station_y);
+        let mut seen = grid(map.size, false);
+        let mut queue = VecDeque::from([(self.x, self.y)]);
+        seen[self.y][self.x] = true;
+
+        while let Some((x, y)) = queue.pop_front() {
+            if !map.explored[y][x] && (x, y) != station && (x, y) != (self.x, self.y) {
+                return Some((x, y));
+            }
+            for (nx, ny) in neighbors(x, y, map.size) {
+                if !seen[ny][nx] && map.step_cost((x, y), (nx, ny), false).is_some() {
+                    seen[ny][nx] = true;
+                    queue.push_back((nx, ny));
                 }
-                path.push(start);
-                path.reverse();
-                return Some(path);
             }
+        }
+        None
+    }
 
-            for &(dx, dy) in &DIRECTIONS {
-                let neighbor_x =
-                    (current.x as isize + dx).max(0).min(MAP_SIZE as isize - 1) as usize;
-                let neighbor_y =
-                    (current.y as isize + dy).max(0).min(MAP_SIZE as isize - 1) as usize;
-                if obstacles[neighbor_y][neighbor_x] || fog_of_war[neighbor_y][neighbor_x] {
-                    continue;
-                }
-                let tentative_g_score = g_score[current.y][current.x] + 1;
-                if tentative_g_score < g_score[neighbor_y][neighbor_x] {
-                    came_from.insert((neighbor_x, neighbor_y), (current.x, current.y));
-                    g_score[neighbor_y][neighbor_x] = tentative_g_score;
-                    f_score[neighbor_y][neighbor_x] =
-                        tentative_g_score + heuristic(neighbor_x, neighbor_y, goal.0, goal.1);
-                    open_list.push(Node::new(
-                        neighbor_x,
-                        neighbor_y,
-                        tentative_g_score,
-                        f_score[neighbor_y][neighbor_x],
-                    ));
+    // Arrivée sur une case : exploration du voisinage et détection des ressources
+    pub(crate) fn visit(&mut self, map: &mut Map, new_x: usize, new_y: usize) {
+        self.x = new_x;
+        self.y = new_y;
+        map.explored[new_y][new_x] = true;
+        // Tempêtes et nuit réduisent la zone découverte comme pour `update_visibility`
+        let range = map.sensor_range_at(new_x, new_y);
+        for y in new_y.saturating_sub(range)..=(new_y + range).min(map.size - 1) {
+            for x in new_x.saturating_sub(range)..=(new_x + range).min(map.size - 1) {
+                if map.fog_of_war[y][x] {
+                    map.fog_of_war[y][x] = false;
+                    map.damage.mark(x, y);
                 }
             }
         }
 
-        None
+        // Les sites déjà signalés à la station sont ignorés
+        if self.reported_sites.contains(&(new_x, new_y)) {
+            return;
+        }
+
+        if map.energy[new_y][new_x] {
+            self.founded_resource = true;
+            self.resource_position = Some((new_x, new_y));
+            map.log(format_args!("Founded energy at ({}, {}).", new_x, new_y));
+        } else if map.minerals[new_y][new_x] {
+            self.founded_resource = true;
+            self.resource_position = Some((new_x, new_y));
+            map.log(format_args!("Founded minerals at ({}, {}).", new_x, new_y));
+        }
+    }
+
+    pub fn return_to_station(&mut self, map: &Map) -> Option<Vec<(usize, usize)>> {
+        self.plan_to(map, (self.station_x, self.station_y))
+    }
+
+    pub fn plan_to(&mut self, map: &Map, goal: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+        planned_path(
+            map,
+            (self.x, self.y),
+            goal,
+            false,
+            &mut self.last_search,
+            &mut self.searches,
+        )
+    }
+}
+
+// Recherche A* d'un chemin, pondérée par le coût de traversée du terrain
+pub fn find_path(
+    map: &Map,
+    start: (usize, usize),
+    goal: (usize, usize),
+    loaded: bool,
+) -> Option<Vec<(usize, usize)>> {
+    search_path(map, start, goal, loaded, None).0
+}
+
+fn search_path(
+    map: &Map,
+    start: (usize, usize),
+    goal: (usize, usize),
+    loaded: bool,
+    mut trace: Option<&mut SearchTrace>,
+) -> (Option<Vec<(usize, usize)>>, usize) {
+    let mut open_list = BinaryHeap::new();
+    let mut came_from = HashMap::new();
+    let mut g_score = grid(map.size, usize::MAX);
+    let mut f_score = grid(map.size, usize::MAX);
+
+    g_score[start.1][start.0] = 0;
+    f_score[start.1][start.0] = heuristic(start.0, start.1, goal.0, goal.1) * MIN_STEP_COST;
+
+    open_list.push(Node::new(start.0, start.1, 0, f_score[start.1][start.0]));
+
+    let mut closed = grid(map.size, false);
+    let mut expanded = 0;
+
+    let path = loop {
+        let current = match open_list.pop() {
+            Some(current) => current,
+            None => break None,
+        };
+        if !closed[current.y][current.x] {
+            expanded += 1;
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.closed.push((current.x, current.y));
+            }
+        }
+        closed[current.y][current.x] = true;
+
+        if current.x == goal.0 && current.y == goal.1 {
+            let mut path = vec![];
+            let mut current_pos = (current.x, current.y);
+            while let Some(&prev) = came_from.get(&current_pos) {
+                path.push(current_pos);
+                current_pos = prev;
+            }
+            path.push(start);
+            path.reverse();
+            break Some(path);
+        }
+
+        for (neighbor_x, neighbor_y) in neighbors(current.x, current.y, map.size) {
+            if map.fog_of_war[neighbor_y][neighbor_x] {
+                continue;
+            }
+            let step_cost =
+                match map.step_cost((current.x, current.y), (neighbor_x, neighbor_y), loaded) {
+                    Some(cost) => cost,
+                    None => continue,
+                };
+            let tentative_g_score = g_score[current.y][current.x] + step_cost;
+            if tentative_g_score < g_score[neighbor_y][neighbor_x] {
+                came_from.insert((neighbor_x, neighbor_y), (current.x, current.y));
+                g_score[neighbor_y][neighbor_x] = tentative_g_score;
+                f_score[neighbor_y][neighbor_x] = tentative_g_score
+                    + heuristic(neighbor_x, neighbor_y, goal.0, goal.1) * MIN_STEP_COST;
+                open_list.push(Node::new(
+                    neighbor_x,
+                    neighbor_y,
+                    tentative_g_score,
+                    f_score[neighbor_y][neighbor_x],
+                ));
+            }
+        }
+    };
+
+    // Ensemble ouvert restant à la fin de la recherche
+    if let Some(trace) = trace {
+        for node in open_list {
+            if !closed[node.y][node.x] && !trace.open.contains(&(node.x, node.y)) {
+                trace.open.push((node.x, node.y));
+            }
+        }
+    }
+    (path, expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hazards::{ActiveEvent, Event, EventKind};
+    use crate::map::tests::{flat_map, place};
+    use crate::mission::{ExplorerStrategy, MissionConfig};
+    use crate::station::ResourceKind;
+
+    #[test]
+    fn storms_stop_fog_from_lifting_around_explorers() {
+        let config = MissionConfig {
+            map_size: 8,
+            explorers: 1,
+            extractors: 1,
+            // Le déplacement aléatoire avance à l'aveugle, sans chemin planifié dans les cases connues
+            strategy: ExplorerStrategy::Random,
+            ..MissionConfig::default()
+        };
+        // Sans gisement à trouver, l'explorateur resterait à la station
+        let mut map = flat_map(config.clone());
+        place(&mut map, (7, 7), ResourceKind::Energy);
+        map.events.push(ActiveEvent {
+            event: Event {
+                kind: EventKind::DustStorm { duration: 100 },
+                x: 4,
+                y: 4,
+                radius: 4,
+            },
+            until: 100,
+        });
+        for _ in 0..40 {
+            map.update_robot();
+        }
+
+        // Seules les cases où l'explorateur est passé sortent du brouillard
+        let explored = map.explored.iter().flatten().filter(|&&e| e).count();
+        assert!(explored > 3);
+        for y in 0..map.size {
+            for x in 0..map.size {
+                assert_eq!(
+                    !map.fog_of_war[y][x],
+                    map.explored[y][x] || (x, y) == (0, 0),
+                    "cell ({}, {})",
+                    x,
+                    y
+                );
+            }
+        }
+
+        // Sans tempête, le voisinage se découvre au même rythme
+        let mut clear = flat_map(config);
+        place(&mut clear, (7, 7), ResourceKind::Energy);
+        for _ in 0..40 {
+            clear.update_robot();
+        }
+        let known = |map: &crate::Map| map.fog_of_war.iter().flatten().filter(|&&f| !f).count();
+        assert!(known(&clear) > known(&map));
     }
 }
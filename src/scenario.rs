@@ -0,0 +1,590 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::brain::{Brain, DEFAULT_SCRIPT_BUDGET};
+use crate::daylight::DayCycle;
+use crate::growth::Growth;
+use crate::hazards::{Event, EventKind, ScheduledEvent};
+use crate::map::DispatchMode;
+use crate::mission::{ExplorerStrategy, MissionConfig, StationPlacement, Victory};
+use crate::terrain::GenerationSettings;
+
+// Fichier de scénario tel qu'il est écrit ; chaque champ absent garde sa valeur par défaut
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ScenarioFile {
+    name: Option<String>,
+    description: Option<String>,
+    seed: Option<u64>,
+    dispatch: Option<String>,
+    map: Option<MapSection>,
+    // Mêmes clés que les arguments `couche.champ=valeur`
+    generation: Option<toml::value::Table>,
+    robots: Option<RobotsSection>,
+    station: Option<StationSection>,
+    victory: Option<VictorySection>,
+    limits: Option<LimitsSection>,
+    scripts: Option<ScriptsSection>,
+    hazards: Option<HazardsSection>,
+    day: Option<DaySection>,
+    growth: Option<GrowthSection>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MapSection {
+    size: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RobotsSection {
+    explorers: Option<usize>,
+    extractors: Option<usize>,
+    strategy: Option<String>,
+    battery_capacity: Option<f32>,
+    battery_recharge: Option<f32>,
+    low_battery: Option<f32>,
+    max_climb: Option<f64>,
+    max_loaded_climb: Option<f64>,
+    sensor_range: Option<usize>,
+    breakdown_rate: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StationSection {
+    placement: String,
+    position: Option<[usize; 2]>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VictorySection {
+    goal: String,
+    energy: Option<usize>,
+    minerals: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LimitsSection {
+    max_ticks: Option<usize>,
+    stall_ticks: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScriptsSection {
+    explorer: Option<String>,
+    extractor: Option<String>,
+    budget: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HazardsSection {
+    storm_rate: Option<f64>,
+    rockfall_rate: Option<f64>,
+    hazard_rate: Option<f64>,
+    hazard_damage: Option<f32>,
+    #[serde(default)]
+    events: Vec<EventSection>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DaySection {
+    length: usize,
+    night: Option<f32>,
+    night_sensor_range: Option<usize>,
+    night_recharge: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GrowthSection {
+    regrow_ticks: Option<usize>,
+    spawn_rate: Option<f64>,
+    spawn_frequency: Option<f64>,
+}
+
+// Événement prévu, `[[hazards.events]]`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EventSection {
+    tick: usize,
+    kind: String,
+    position: [usize; 2],
+    #[serde(default)]
+    radius: usize,
+    duration: Option<usize>,
+    damage: Option<f32>,
+}
+
+// Mission décrite par un fichier de scénario, validée au chargement
+#[derive(Clone, Debug)]
+pub struct Scenario {
+    pub name: String,
+    pub description: Option<String>,
+    pub seed: Option<u64>,
+    pub dispatch_mode: Option<DispatchMode>,
+    pub config: MissionConfig,
+    pub settings: GenerationSettings,
+    // Paramètres de génération du scénario, sous la forme `couche.champ`
+    pub overrides: Vec<(String, String)>,
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Scenario, String> {
+        let text =
+            fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        Scenario::parse(&text, dir).map_err(|error| {
+            let name = path.display();
+            format!(
+                "{}: {}",
+                name,
+                error.replace('\n', &format!("\n{}: ", name))
+            )
+        })
+    }
+
+    // Les chemins des scripts sont relatifs au dossier `dir` du scénario
+    pub fn parse(text: &str, dir: &Path) -> Result<Scenario, String> {
+        let file: ScenarioFile = toml::from_str(text).map_err(|error| error.to_string())?;
+        let mut errors = vec![];
+
+        let mut scenario = Scenario {
+            name: file.name.unwrap_or_else(|| "Unnamed scenario".to_string()),
+            description: file.description,
+            seed: file.seed,
+            dispatch_mode: None,
+            config: MissionConfig::default(),
+            settings: GenerationSettings::default(),
+            overrides: vec![],
+        };
+        let config = &mut scenario.config;
+
+        match file.dispatch.as_deref() {
+            None => {}
+            Some("serialized") => scenario.dispatch_mode = Some(DispatchMode::Serialized),
+            Some("concurrent") => scenario.dispatch_mode = Some(DispatchMode::Concurrent),
+            Some(other) => errors.push(format!(
+                "dispatch: expected 'serialized' or 'concurrent', got '{}'",
+                other
+            )),
+        }
+
+        if let Some(map) = file.map {
+            config.map_size = map.size.unwrap_or(config.map_size);
+        }
+
+        for (key, value) in flatten_table(file.generation.unwrap_or_default(), "") {
+            let value = match value {
+                toml::Value::Integer(value) => value.to_string(),
+                toml::Value::Float(value) => value.to_string(),
+                other => {
+                    errors.push(format!(
+                        "generation.{}: expected a number, got {}",
+                        key, other
+                    ));
+                    continue;
+                }
+            };
+            match scenario.settings.set(&key, &value) {
+                Ok(()) => scenario.overrides.push((key, value)),
+                Err(error) => errors.push(format!("generation: {}", error)),
+            }
+        }
+        if let Err(error) = scenario.settings.validate() {
+            errors.push(format!("generation: {}", error));
+        }
+
+        if let Some(robots) = file.robots {
+            let capabilities = &mut config.robots;
+            config.explorers = robots.explorers.unwrap_or(config.explorers);
+            config.extractors = robots.extractors.unwrap_or(config.extractors);
+            if let Some(strategy) = robots.strategy {
+                match ExplorerStrategy::parse(&strategy) {
+                    Ok(strategy) => config.strategy = strategy,
+                    Err(error) => errors.push(format!("robots: {}", error)),
+                }
+            }
+            capabilities.battery_capacity = robots
+                .battery_capacity
+                .unwrap_or(capabilities.battery_capacity);
+            capabilities.battery_recharge = robots
+                .battery_recharge
+                .unwrap_or(capabilities.battery_recharge);
+            capabilities.low_battery = robots.low_battery.unwrap_or(capabilities.low_battery);
+            capabilities.max_climb = robots.max_climb.unwrap_or(capabilities.max_climb);
+            capabilities.max_loaded_climb = robots
+                .max_loaded_climb
+                .unwrap_or(capabilities.max_loaded_climb);
+            capabilities.sensor_range = robots.sensor_range.unwrap_or(capabilities.sensor_range);
+            capabilities.breakdown_rate =
+                robots.breakdown_rate.unwrap_or(capabilities.breakdown_rate);
+        }
+
+        if let Some(station) = file.station {
+            match (station.placement.as_str(), station.position) {
+                ("random", None) => config.station = StationPlacement::Random,
+                ("center", None) => config.station = StationPlacement::Center,
+                ("fixed", Some([x, y])) => config.station = StationPlacement::Fixed(x, y),
+                ("fixed", None) => {
+                    errors.push("station: a fixed placement needs a position = [x, y]".to_string())
+                }
+                ("random" | "center", Some(_)) => errors.push(format!(
+                    "station: position is only used with placement = \"fixed\", not \"{}\"",
+                    station.placement
+                )),
+                (other, _) => errors.push(format!(
+                    "station: expected placement 'random', 'center' or 'fixed', got '{}'",
+                    other
+                )),
+            }
+        }
+
+        if let Some(victory) = file.victory {
+            match victory.goal.as_str() {
+                "all_resources" if victory.energy.is_none() && victory.minerals.is_none() => {
+                    config.victory = Victory::AllResources
+                }
+                "all_resources" => errors.push(
+                    "victory: energy and minerals targets are only used with goal = \"stockpile\""
+                        .to_string(),
+                ),
+                "stockpile" => {
+                    config.victory = Victory::Stockpile {
+                        energy: victory.energy.unwrap_or(0),
+                        minerals: victory.minerals.unwrap_or(0),
+                    }
+                }
+                "endless" if victory.energy.is_none() && victory.minerals.is_none() => {
+                    config.victory = Victory::Endless
+                }
+                "endless" => errors.push(
+                    "victory: energy and minerals targets are only used with goal = \"stockpile\""
+                        .to_string(),
+                ),
+                other => errors.push(format!(
+                    "victory: expected goal 'all_resources', 'stockpile' or 'endless', got '{}'",
+                    other
+                )),
+            }
+        }
+
+        if let Some(limits) = file.limits {
+            config.max_ticks = limits.max_ticks.unwrap_or(config.max_ticks);
+            config.stall_ticks = limits.stall_ticks.unwrap_or(config.stall_ticks);
+        }
+
+        if let Some(scripts) = file.scripts {
+            let budget = scripts.budget.unwrap_or(DEFAULT_SCRIPT_BUDGET);
+            if budget == 0 {
+                errors.push("scripts: budget must be positive".to_string());
+            }
+            for (slot, path) in [
+                (&mut config.scripts.explorer, scripts.explorer),
+                (&mut config.scripts.extractor, scripts.extractor),
+            ] {
+                if let Some(path) = path {
+                    match Brain::load(&dir.join(path), budget) {
+                        Ok(brain) => *slot = Some(brain),
+                        Err(error) => errors.push(format!("scripts: {}", error)),
+                    }
+                }
+            }
+        }
+
+        if let Some(hazards) = file.hazards {
+            let settings = &mut config.hazards;
+            settings.storm_rate = hazards.storm_rate.unwrap_or(settings.storm_rate);
+            settings.rockfall_rate = hazards.rockfall_rate.unwrap_or(settings.rockfall_rate);
+            settings.hazard_rate = hazards.hazard_rate.unwrap_or(settings.hazard_rate);
+            settings.hazard_damage = hazards.hazard_damage.unwrap_or(settings.hazard_damage);
+            for event in hazards.events {
+                let kind = match (event.kind.as_str(), event.duration, event.damage) {
+                    ("dust_storm", Some(duration), None) => EventKind::DustStorm { duration },
+                    ("rockfall", None, None) => EventKind::Rockfall,
+                    ("hazard", Some(duration), damage) => EventKind::Hazard {
+                        duration,
+                        damage: damage.unwrap_or(settings.hazard_damage),
+                    },
+                    ("dust_storm" | "hazard", None, _) => {
+                        errors.push(format!(
+                            "hazards: the {} at tick {} needs a duration",
+                            event.kind, event.tick
+                        ));
+                        continue;
+                    }
+                    ("dust_storm" | "rockfall", _, _) => {
+                        errors.push(format!(
+                            "hazards: unexpected duration or damage for the {} at tick {}",
+                            event.kind, event.tick
+                        ));
+                        continue;
+                    }
+                    (other, _, _) => {
+                        errors.push(format!(
+                            "hazards: expected event kind 'dust_storm', 'rockfall' or 'hazard', got '{}'",
+                            other
+                        ));
+                        continue;
+                    }
+                };
+                let [x, y] = event.position;
+                settings.scheduled.push(ScheduledEvent {
+                    tick: event.tick,
+                    event: Event {
+                        kind,
+                        x,
+                        y,
+                        radius: event.radius,
+                    },
+                });
+            }
+        }
+
+        if let Some(day) = file.day {
+            let defaults = DayCycle::default();
+            config.day = DayCycle {
+                length: day.length,
+                night: day.night.unwrap_or(defaults.night),
+                night_sensor_range: day
+                    .night_sensor_range
+                    .unwrap_or(defaults.night_sensor_range),
+                night_recharge: day.night_recharge.unwrap_or(defaults.night_recharge),
+            };
+        }
+
+        if let Some(growth) = file.growth {
+            let defaults = Growth::default();
+            config.growth = Growth {
+                regrow_ticks: growth.regrow_ticks.unwrap_or(defaults.regrow_ticks),
+                spawn_rate: growth.spawn_rate.unwrap_or(defaults.spawn_rate),
+                spawn_frequency: growth.spawn_frequency.unwrap_or(defaults.spawn_frequency),
+            };
+        }
+
+        if let Err(error) = config.validate() {
+            errors.extend(error.lines().map(str::to_string));
+        }
+        if errors.is_empty() {
+            Ok(scenario)
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+}
+
+// Aplatit les tables imbriquées : [generation.elevation] frequency devient `elevation.frequency`
+fn flatten_table(table: toml::value::Table, prefix: &str) -> Vec<(String, toml::Value)> {
+    let mut entries = vec![];
+    for (key, value) in table {
+        let key = format!("{}{}", prefix, key);
+        match value {
+            toml::Value::Table(table) => entries.extend(flatten_table(table, &format!("{}.", key))),
+            value => entries.push((key, value)),
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Scenario, String> {
+        Scenario::parse(text, Path::new("scenarios"))
+    }
+
+    fn errors(text: &str) -> Vec<String> {
+        match parse(text) {
+            Ok(_) => panic!("the scenario should be rejected"),
+            Err(error) => error.lines().map(str::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn parses_a_complete_scenario() {
+        let scenario = parse(
+            r#"
+            name = "Canyon"
+            seed = 12
+            dispatch = "concurrent"
+
+            [map]
+            size = 20
+
+            [generation.elevation]
+            frequency = 0.2
+
+            [robots]
+            explorers = 2
+            strategy = "frontier"
+            sensor_range = 3
+
+            [station]
+            placement = "fixed"
+            position = [4, 5]
+
+            [victory]
+            goal = "stockpile"
+            energy = 3
+
+            [limits]
+            max_ticks = 900
+
+            [hazards]
+            hazard_damage = 2.5
+
+            [[hazards.events]]
+            tick = 40
+            kind = "hazard"
+            position = [6, 7]
+            radius = 2
+            duration = 30
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(scenario.name, "Canyon");
+        assert_eq!(scenario.seed, Some(12));
+        assert_eq!(scenario.dispatch_mode, Some(DispatchMode::Concurrent));
+        assert_eq!(
+            scenario.overrides,
+            vec![("elevation.frequency".to_string(), "0.2".to_string())]
+        );
+        let config = &scenario.config;
+        assert_eq!(config.map_size, 20);
+        assert_eq!(config.explorers, 2);
+        assert_eq!(config.extractors, MissionConfig::default().extractors);
+        assert_eq!(config.strategy, ExplorerStrategy::Frontier);
+        assert_eq!(config.robots.sensor_range, 3);
+        assert_eq!(config.station, StationPlacement::Fixed(4, 5));
+        assert_eq!(
+            config.victory,
+            Victory::Stockpile {
+                energy: 3,
+                minerals: 0
+            }
+        );
+        assert_eq!(config.max_ticks, 900);
+        assert_eq!(
+            config.hazards.scheduled,
+            vec![ScheduledEvent {
+                tick: 40,
+                event: Event {
+                    kind: EventKind::Hazard {
+                        duration: 30,
+                        damage: 2.5
+                    },
+                    x: 6,
+                    y: 7,
+                    radius: 2,
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_inconsistent_station_placements() {
+        assert_eq!(
+            errors("[station]\nplacement = \"fixed\""),
+            ["station: a fixed placement needs a position = [x, y]"]
+        );
+        assert_eq!(
+            errors("[station]\nplacement = \"center\"\nposition = [1, 1]"),
+            ["station: position is only used with placement = \"fixed\", not \"center\""]
+        );
+        assert_eq!(
+            errors("[station]\nplacement = \"corner\""),
+            ["station: expected placement 'random', 'center' or 'fixed', got 'corner'"]
+        );
+        assert_eq!(
+            errors("[map]\nsize = 8\n[station]\nplacement = \"fixed\"\nposition = [8, 2]"),
+            ["station position (8, 2) is outside the 8x8 map"]
+        );
+    }
+
+    #[test]
+    fn rejects_inconsistent_victory_goals() {
+        assert_eq!(
+            errors("[victory]\ngoal = \"endless\"\nenergy = 4"),
+            ["victory: energy and minerals targets are only used with goal = \"stockpile\""]
+        );
+        assert_eq!(
+            errors("[victory]\ngoal = \"stockpile\""),
+            ["a stockpile victory needs an energy or minerals target"]
+        );
+        assert_eq!(
+            errors("[victory]\ngoal = \"survive\""),
+            ["victory: expected goal 'all_resources', 'stockpile' or 'endless', got 'survive'"]
+        );
+    }
+
+    #[test]
+    fn rejects_inconsistent_events() {
+        let event = |fields: &str| {
+            errors(&format!(
+                "[[hazards.events]]\ntick = 10\nposition = [1, 1]\n{}",
+                fields
+            ))
+        };
+        assert_eq!(
+            event("kind = \"dust_storm\""),
+            ["hazards: the dust_storm at tick 10 needs a duration"]
+        );
+        assert_eq!(
+            event("kind = \"rockfall\"\ndamage = 1.0"),
+            ["hazards: unexpected duration or damage for the rockfall at tick 10"]
+        );
+        assert_eq!(
+            event("kind = \"flood\""),
+            ["hazards: expected event kind 'dust_storm', 'rockfall' or 'hazard', got 'flood'"]
+        );
+    }
+
+    #[test]
+    fn reports_every_error_at_once() {
+        assert_eq!(
+            errors(
+                "dispatch = \"eager\"\n[robots]\nexplorers = 0\nstrategy = \"spiral\"\n[limits]\nmax_ticks = 0"
+            ),
+            [
+                "dispatch: expected 'serialized' or 'concurrent', got 'eager'",
+                "robots: unknown explorer strategy 'spiral', expected 'random' or 'frontier'",
+                "at least one explorer is required",
+                "max_ticks must be positive",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let error = parse("[robots]\nwheels = 6").unwrap_err();
+        assert!(error.contains("unknown field `wheels`"), "{}", error);
+    }
+
+    #[test]
+    fn bundled_scenarios_load() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios");
+        let mut loaded = 0;
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "toml")
+            {
+                if let Err(error) = Scenario::load(&path) {
+                    panic!("{}", error);
+                }
+                loaded += 1;
+            }
+        }
+        assert!(loaded > 0);
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::camera::RobotRef;
+
+// Nature d'une ressource transportée ou stockée
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ResourceKind {
+    Energy,
+    Minerals,
+}
+
+// La station centralise les sites découverts et les distribue aux extracteurs
+#[derive(Clone)]
+pub struct Station {
+    pub x: usize,
+    pub y: usize,
+    pub pending_sites: VecDeque<(usize, usize)>,
+    pub known_sites: HashSet<(usize, usize)>,
+    // Robots en panne en attente d'un dépanneur
+    pub rescues: VecDeque<(RobotRef, (usize, usize))>,
+    pub energy: usize,
+    pub minerals: usize,
+}
+
+impl Station {
+    pub fn new(x: usize, y: usize) -> Self {
+        Station {
+            x,
+            y,
+            pending_sites: VecDeque::new(),
+            known_sites: HashSet::new(),
+            rescues: VecDeque::new(),
+            energy: 0,
+            minerals: 0,
+        }
+    }
+
+    // Enregistre un site signalé par un explorateur, sauf s'il est déjà connu
+    pub fn report_site(&mut self, position: (usize, usize)) -> bool {
+        if !self.known_sites.insert(position) {
+            return false;
+        }
+        self.pending_sites.push_back(position);
+        true
+    }
+
+    // Attribue un site en attente à un extracteur
+    pub fn take_site(&mut self, index: usize) -> Option<(usize, usize)> {
+        self.pending_sites.remove(index)
+    }
+
+    // Dépose la cargaison d'un extracteur dans les stocks de la station
+    pub fn deposit(&mut self, kind: ResourceKind) {
+        match kind {
+            ResourceKind::Energy => self.energy += 1,
+            ResourceKind::Minerals => self.minerals += 1,
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+
+use crate::{ENERGY_THRESHOLD, MINERALS_THRESHOLD, OBSTACLE_THRESHOLD};
+
+// Paramètres d'une couche de bruit fractal
+#[derive(Copy, Clone, Debug)]
+pub struct NoiseSettings {
+    pub frequency: f64,
+    pub octaves: usize,
+    pub lacunarity: f64,
+    pub persistence: f64,
+}
+
+impl NoiseSettings {
+    pub const fn new(frequency: f64, octaves: usize) -> Self {
+        NoiseSettings {
+            frequency,
+            octaves,
+            lacunarity: 2.0,
+            persistence: 0.5,
+        }
+    }
+
+    pub fn build(&self, seed: u32) -> Fbm<Perlin> {
+        Fbm::<Perlin>::new(seed)
+            .set_octaves(self.octaves)
+            .set_frequency(self.frequency)
+            .set_lacunarity(self.lacunarity)
+            .set_persistence(self.persistence)
+    }
+}
+
+// Échantillonne une couche de bruit ramenée dans [0, 1] (le fBm varie environ dans [-2, 2])
+pub fn sample01(noise: &Fbm<Perlin>, x: usize, y: usize) -> f64 {
+    ((noise.get([x as f64, y as f64]) / 2.0 + 1.0) / 2.0).clamp(0.0, 1.0)
+}
+
+// Paramètres du pipeline de génération procédurale
+#[derive(Clone, Debug)]
+pub struct GenerationSettings {
+    pub elevation: NoiseSettings,
+    pub moisture: NoiseSettings,
+    pub obstacles: NoiseSettings,
+    pub resources: NoiseSettings,
+    pub obstacle_threshold: f64,
+    pub energy_threshold: f64,
+    pub minerals_threshold: f64,
+}
+
+impl Default for GenerationSettings {
+    fn default() -> Self {
+        GenerationSettings {
+            elevation: NoiseSettings::new(0.12, 4),
+            moisture: NoiseSettings::new(0.08, 3),
+            obstacles: NoiseSettings::new(0.35, 3),
+            resources: NoiseSettings::new(0.3, 2),
+            obstacle_threshold: OBSTACLE_THRESHOLD,
+            energy_threshold: ENERGY_THRESHOLD,
+            minerals_threshold: MINERALS_THRESHOLD,
+        }
+    }
+}
+
+impl GenerationSettings {
+    // Modifie un paramètre à partir d'une paire `couche.champ=valeur`
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("invalid value '{}' for '{}'", value, key);
+        let (layer, field) = key.split_once('.').unwrap_or((key, ""));
+
+        let noise = match layer {
+            "elevation" => &mut self.elevation,
+            "moisture" => &mut self.moisture,
+            "obstacles" => &mut self.obstacles,
+            "resources" => &mut self.resources,
+            "obstacle_threshold" => {
+                self.obstacle_threshold = value.parse().map_err(|_| invalid())?;
+                return Ok(());
+            }
+            "energy_threshold" => {
+                self.energy_threshold = value.parse().map_err(|_| invalid())?;
+                return Ok(());
+            }
+            "minerals_threshold" => {
+                self.minerals_threshold = value.parse().map_err(|_| invalid())?;
+                return Ok(());
+            }
+            _ => return Err(format!("unknown generation setting '{}'", key)),
+        };
+
+        match field {
+            "frequency" => noise.frequency = value.parse().map_err(|_| invalid())?,
+            "octaves" => noise.octaves = value.parse().map_err(|_| invalid())?,
+            "lacunarity" => noise.lacunarity = value.parse().map_err(|_| invalid())?,
+            "persistence" => noise.persistence = value.parse().map_err(|_| invalid())?,
+            _ => return Err(format!("unknown generation setting '{}'", key)),
+        }
+        Ok(())
+    }
+
+    // Refuse les couches de bruit dégénérées
+    pub fn validate(&self) -> Result<(), String> {
+        let layers = [
+            ("elevation", &self.elevation),
+            ("moisture", &self.moisture),
+            ("obstacles", &self.obstacles),
+            ("resources", &self.resources),
+        ];
+        for (name, noise) in layers {
+            if noise.frequency <= 0.0 || noise.lacunarity <= 0.0 {
+                return Err(format!(
+                    "{}: frequency and lacunarity must be positive",
+                    name
+                ));
+            }
+            if !(1..=Fbm::<Perlin>::MAX_OCTAVES).contains(&noise.octaves) {
+                return Err(format!(
+                    "{}: octaves must be between 1 and {}, got {}",
+                    name,
+                    Fbm::<Perlin>::MAX_OCTAVES,
+                    noise.octaves
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Biomes déterminés par l'altitude et l'humidité
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Biome {
+    Crater,
+    Plains,
+    Ridge,
+    Ice,
+}
+
+impl Biome {
+    pub const ALL: [Biome; 4] = [Biome::Crater, Biome::Plains, Biome::Ridge, Biome::Ice];
+
+    pub fn classify(elevation: f64, moisture: f64) -> Biome {
+        if elevation < 0.35 {
+            Biome::Crater
+        } else if elevation > 0.65 {
+            Biome::Ridge
+        } else if moisture > 0.6 {
+            Biome::Ice
+        } else {
+            Biome::Plains
+        }
+    }
+
+    // Décalage du seuil d'obstacle : positif = plus d'obstacles
+    pub fn obstacle_bias(self) -> f64 {
+        match self {
+            Biome::Crater => 0.05,
+            Biome::Plains => -0.25,
+            Biome::Ridge => 0.35,
+            Biome::Ice => 0.0,
+        }
+    }
+
+    // Décalage du seuil d'énergie : positif = plus de gisements
+    pub fn energy_bias(self) -> f64 {
+        match self {
+            Biome::Crater => 0.1,
+            Biome::Plains => 0.0,
+            Biome::Ridge => -0.1,
+            Biome::Ice => 0.25,
+        }
+    }
+
+    // Décalage du seuil de minerais : positif = plus de gisements
+    pub fn minerals_bias(self) -> f64 {
+        match self {
+            Biome::Crater => 0.3,
+            Biome::Plains => -0.05,
+            Biome::Ridge => 0.2,
+            Biome::Ice => -0.1,
+        }
+    }
+
+    // Coût de base pour entrer dans une case : le sable ralentit, la roche est rapide
+    pub fn base_cost(self) -> usize {
+        match self {
+            Biome::Crater => 20,
+            Biome::Plains => 30,
+            Biome::Ridge => 10,
+            Biome::Ice => 15,
+        }
+    }
+
+    pub fn symbol(self) -> char {
+        match self {
+            Biome::Crater => 'o',
+            Biome::Plains => '.',
+            Biome::Ridge => '^',
+            Biome::Ice => '*',
+        }
+    }
+}